@@ -0,0 +1,84 @@
+use {
+    criterion::{BenchmarkId, Criterion, criterion_group, criterion_main},
+    hlc_gen::{
+        HlcGenerator,
+        config::OverflowPolicy,
+        source::{CachedClock, ClockSource, UtcClock},
+    },
+    std::{
+        hint::black_box,
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    },
+};
+
+/// Number of threads used in multi-threaded benchmarks.
+const THREADS: [usize; 3] = [2, 4, 8];
+
+fn current_timestamp(c: &mut Criterion) {
+    let mut group = c.benchmark_group("current_timestamp");
+    group.bench_function("utc", |b| b.iter(|| UtcClock.current_timestamp()));
+    let clock = CachedClock::new();
+    group.bench_function("cached", |b| b.iter(|| clock.current_timestamp()));
+    group.finish();
+}
+
+/// Measures `iters` timestamps generated by `threads` threads sharing the
+/// generator.
+fn generate<S: ClockSource + Send + Sync + 'static>(
+    g: &Arc<HlcGenerator<S>>,
+    threads: usize,
+    iters: u64,
+) -> Duration {
+    let per_thread = iters.div_ceil(threads as u64);
+    let start = Instant::now();
+    let handles = (0..threads)
+        .map(|_| {
+            let g = g.clone();
+            thread::spawn(move || {
+                for _ in 0..per_thread {
+                    black_box(g.next_timestamp());
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    start.elapsed()
+}
+
+fn next_timestamp(c: &mut Criterion) {
+    // Borrowing keeps generation going, when the logical clock overflows
+    // within a (cached) millisecond.
+    let utc = Arc::new(
+        HlcGenerator::builder()
+            .overflow_policy(OverflowPolicy::BorrowNext)
+            .build()
+            .unwrap(),
+    );
+    let cached = Arc::new(
+        HlcGenerator::builder()
+            .clock(CachedClock::new())
+            .overflow_policy(OverflowPolicy::BorrowNext)
+            .build()
+            .unwrap(),
+    );
+
+    let mut group = c.benchmark_group("next_timestamp");
+    group.bench_function("utc", |b| b.iter(|| utc.next_timestamp()));
+    group.bench_function("cached", |b| b.iter(|| cached.next_timestamp()));
+    for threads in THREADS {
+        group.bench_with_input(BenchmarkId::new("utc", threads), &threads, |b, &n| {
+            b.iter_custom(|iters| generate(&utc, n, iters))
+        });
+        group.bench_with_input(BenchmarkId::new("cached", threads), &threads, |b, &n| {
+            b.iter_custom(|iters| generate(&cached, n, iters))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, current_timestamp, next_timestamp);
+criterion_main!(benches);
@@ -0,0 +1,206 @@
+use {
+    crate::{
+        HlcGenerator,
+        HlcTimestampOf,
+        config::{DriftPolicy, HlcConfig, OverflowPolicy},
+        epoch::{DefaultEpoch, Epoch},
+        error::{HlcError, HlcResult},
+        layout::{DefaultLayout, Layout},
+        persist::{HighWaterMark, Persistence},
+        source::{ClockSource, UtcClock},
+    },
+    std::marker::PhantomData,
+};
+
+/// Builder of the [`HlcGenerator`].
+///
+/// Collects all the options of the generator, and validates their
+/// combination on [`build()`](HlcGeneratorBuilder::build):
+///
+/// ```
+/// use hlc_gen::{HlcGenerator, config::OverflowPolicy, layout::BitLayout, source::ManualClock};
+///
+/// let g = HlcGenerator::builder()
+///     .clock(ManualClock::new(1_746_057_600_000))
+///     .layout::<BitLayout<42, 12, 10>>()
+///     .node_id(42)
+///     .max_drift(1000)
+///     .overflow_policy(OverflowPolicy::BorrowNext)
+///     .build()
+///     .unwrap();
+/// let ts = g.next_timestamp().unwrap();
+/// assert_eq!((ts.timestamp(), ts.node()), (1_746_057_600_000, 42));
+/// ```
+///
+/// Options can also be loaded from [`HlcConfig`] (which is deserializable,
+/// when the `serde` feature is enabled) with
+/// [`config()`](HlcGeneratorBuilder::config).
+pub struct HlcGeneratorBuilder<
+    S: ClockSource = UtcClock,
+    L: Layout = DefaultLayout,
+    E: Epoch = DefaultEpoch,
+> {
+    /// The timestamp provider used to get the current timestamp.
+    clock: S,
+
+    /// Options of the generator.
+    config: HlcConfig,
+
+    /// Timestamp (in milliseconds since the Unix epoch) and logical clock
+    /// count to start from.
+    initial_state: Option<(i64, u64)>,
+
+    /// Persistence of the high-water mark, and the reserved window.
+    persistence: Option<(Box<dyn Persistence>, u64)>,
+
+    _marker: PhantomData<(L, E)>,
+}
+
+impl Default for HlcGeneratorBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HlcGeneratorBuilder {
+    /// Creates a new builder with default options, UTC clock, default layout
+    /// and default epoch.
+    pub fn new() -> Self {
+        Self {
+            clock: UtcClock,
+            config: HlcConfig::default(),
+            initial_state: None,
+            persistence: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<S: ClockSource, L: Layout, E: Epoch> HlcGeneratorBuilder<S, L, E> {
+    /// Sets the clock source.
+    pub fn clock<S2: ClockSource>(self, clock: S2) -> HlcGeneratorBuilder<S2, L, E> {
+        HlcGeneratorBuilder {
+            clock,
+            config: self.config,
+            initial_state: self.initial_state,
+            persistence: self.persistence,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the bit layout of the generated timestamps.
+    pub fn layout<L2: Layout>(self) -> HlcGeneratorBuilder<S, L2, E> {
+        HlcGeneratorBuilder {
+            clock: self.clock,
+            config: self.config,
+            initial_state: self.initial_state,
+            persistence: self.persistence,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets the epoch of the generated timestamps.
+    pub fn epoch<E2: Epoch>(self) -> HlcGeneratorBuilder<S, L, E2> {
+        HlcGeneratorBuilder {
+            clock: self.clock,
+            config: self.config,
+            initial_state: self.initial_state,
+            persistence: self.persistence,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Sets all the options at once, e.g. loaded from a configuration file.
+    pub fn config(mut self, config: HlcConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the maximum drift (in milliseconds), see
+    /// [`HlcConfig::max_drift`].
+    pub fn max_drift(mut self, max_drift: usize) -> Self {
+        self.config.max_drift = max_drift;
+        self
+    }
+
+    /// Sets the maximum lag (in milliseconds), see [`HlcConfig::max_lag`].
+    pub fn max_lag(mut self, max_lag: usize) -> Self {
+        self.config.max_lag = max_lag;
+        self
+    }
+
+    /// Sets the drift policy, see [`HlcConfig::drift_policy`].
+    pub fn drift_policy(mut self, drift_policy: DriftPolicy) -> Self {
+        self.config.drift_policy = drift_policy;
+        self
+    }
+
+    /// Sets the node identifier, see [`HlcConfig::node_id`].
+    pub fn node_id(mut self, node_id: u64) -> Self {
+        self.config.node_id = node_id;
+        self
+    }
+
+    /// Sets the overflow policy, see [`HlcConfig::overflow_policy`].
+    pub fn overflow_policy(mut self, overflow_policy: OverflowPolicy) -> Self {
+        self.config.overflow_policy = overflow_policy;
+        self
+    }
+
+    /// Sets the state to start from (e.g. the last timestamp known to be
+    /// issued by the previous incarnation of the generator), given as
+    /// timestamp (in milliseconds since the Unix epoch) and logical clock
+    /// count.
+    ///
+    /// The state is only used if it is ahead of the wall-clock time.
+    pub fn initial_state(mut self, timestamp: i64, count: u64) -> Self {
+        self.initial_state = Some((timestamp, count));
+        self
+    }
+
+    /// Enables persistence of the high-water mark, reserving `window`
+    /// milliseconds ahead, see
+    /// [`HlcGenerator::with_persistence()`](HlcGenerator::with_persistence).
+    pub fn persistence(mut self, persistence: impl Persistence + 'static, window: u64) -> Self {
+        self.persistence = Some((Box::new(persistence), window));
+        self
+    }
+
+    /// Builds the generator.
+    ///
+    /// An error is returned if:
+    /// - the node identifier does not fit into the layout
+    ///   ([`HlcError::NodeIdExceedsMax`]);
+    /// - the initial state cannot be represented in the layout and epoch;
+    /// - the initial state is ahead of the wall-clock time by more than the
+    ///   maximum drift ([`HlcError::DriftTooLarge`]);
+    /// - the persistence window is zero ([`HlcError::InvalidConfig`]), or the
+    ///   high-water mark cannot be loaded ([`HlcError::Persistence`]).
+    pub fn build(self) -> HlcResult<HlcGenerator<S, L, E>> {
+        let initial_state = match self.initial_state {
+            Some((timestamp, count)) => {
+                let ts = HlcTimestampOf::<L, E>::from_parts_with_node(
+                    timestamp,
+                    count,
+                    self.config.node_id,
+                )?;
+                let max_drift = self.config.max_drift;
+                if max_drift > 0 {
+                    let now = self.clock.current_timestamp();
+                    let skew = timestamp.saturating_sub(now);
+                    if usize::try_from(skew).is_ok_and(|drift| drift > max_drift) {
+                        return Err(HlcError::DriftTooLarge(timestamp, now, skew, max_drift));
+                    }
+                }
+                Some(ts)
+            }
+            None => None,
+        };
+        let high_water_mark = self
+            .persistence
+            .map(|(persistence, window)| HighWaterMark::load(persistence, window))
+            .transpose()?;
+
+        HlcGenerator::init(self.config, self.clock, initial_state, high_water_mark)
+    }
+}
@@ -0,0 +1,126 @@
+/// Configuration of the [`HlcGenerator`](crate::HlcGenerator).
+///
+/// Use struct update syntax to set only the options you need:
+///
+/// ```
+/// use hlc_gen::config::HlcConfig;
+///
+/// let config = HlcConfig {
+///     max_drift: 1000,
+///     ..Default::default()
+/// };
+/// assert_eq!(config.node_id, 0);
+/// ```
+///
+/// With the `serde` feature enabled, the configuration can be loaded from
+/// service config files (missing options take default values), and passed to
+/// [`HlcGeneratorBuilder::config()`](crate::HlcGeneratorBuilder::config).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, deny_unknown_fields))]
+pub struct HlcConfig {
+    /// The maximum drift (in milliseconds) allowed between the physical clock
+    /// and the wall-clock time.
+    ///
+    /// Set to 0 to disable the drift check.
+    pub max_drift: usize,
+
+    /// The maximum lag (in milliseconds) allowed between the wall-clock time
+    /// and the incoming timestamps, i.e. how stale a message can be.
+    ///
+    /// Set to 0 to disable the lag check.
+    pub max_lag: usize,
+
+    /// What to do when an incoming timestamp exceeds the maximum drift or
+    /// lag.
+    pub drift_policy: DriftPolicy,
+
+    /// Identifier of the node, embedded into every generated timestamp.
+    ///
+    /// Must fit into the [`NODE_BITS`](crate::layout::Layout::NODE_BITS) of
+    /// the generator's layout. The default layout reserves no bits for node
+    /// identifiers, so only 0 is accepted.
+    pub node_id: u64,
+
+    /// What to do when the logical clock overflows, i.e. when more than
+    /// `LC_MAX` timestamps are requested within the same millisecond.
+    pub overflow_policy: OverflowPolicy,
+}
+
+/// Logical clock overflow handling policy.
+///
+/// Overflows are counted regardless of the selected policy, see
+/// [`HlcGenerator::overflows()`](crate::HlcGenerator::overflows).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum OverflowPolicy {
+    /// Fail to produce a timestamp.
+    ///
+    /// [`next_timestamp()`](crate::HlcGenerator::next_timestamp) returns
+    /// `None`, and
+    /// [`try_next_timestamp()`](crate::HlcGenerator::try_next_timestamp)
+    /// returns
+    /// [`LogicalClockOverflow`](crate::error::HlcError::LogicalClockOverflow).
+    #[default]
+    Error,
+
+    /// Borrow the next millisecond, i.e. advance physical time ahead of the
+    /// wall-clock time and reset the logical clock.
+    ///
+    /// Physical time catches up with the wall-clock time, once the burst is
+    /// over.
+    BorrowNext,
+
+    /// Yield the current thread until the wall-clock time ticks past the
+    /// physical time of the generator.
+    ///
+    /// If the wall-clock time precedes the epoch, the request fails instead.
+    Wait,
+}
+
+/// Drift handling policy, applied by
+/// [`HlcGenerator::update()`](crate::HlcGenerator::update) when an incoming
+/// timestamp is ahead of the wall-clock time by more than the maximum drift,
+/// or lags behind it by more than the maximum lag.
+///
+/// Drift violations are counted regardless of the selected policy, see
+/// [`HlcGenerator::drift_violations()`](crate::HlcGenerator::drift_violations).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum DriftPolicy {
+    /// Reject the incoming timestamp with
+    /// [`DriftTooLarge`](crate::error::HlcError::DriftTooLarge), leaving the
+    /// generator state intact.
+    #[default]
+    Reject,
+
+    /// Accept the incoming timestamp, but advance physical time at most by the
+    /// maximum drift ahead of the wall-clock time (stale timestamps are
+    /// accepted as is, as they never advance physical time).
+    ///
+    /// The updated timestamp is still ordered after all the timestamps issued
+    /// by the generator, but not necessarily after the incoming one, i.e. a
+    /// peer with a bad clock cannot drag the generator arbitrarily far ahead,
+    /// at the cost of the causality with that peer. Clamped timestamps are
+    /// counted by [`HlcGenerator::clamped()`](crate::HlcGenerator::clamped).
+    Clamp,
+
+    /// Accept the incoming timestamp as is (as if the drift check is
+    /// disabled), only counting the violation.
+    Report,
+
+    /// Reject the incoming timestamp, and quarantine its sender (identified by
+    /// the node identifier embedded into the timestamp, or the peer
+    /// identifier given to
+    /// [`update_from()`](crate::HlcGenerator::update_from)): all the subsequent
+    /// timestamps of the sender are rejected with
+    /// [`PeerQuarantined`](crate::error::HlcError::PeerQuarantined), until it
+    /// is [released](crate::HlcGenerator::release_peer).
+    ///
+    /// Unless the peer identifiers are given to `update_from()`, requires a
+    /// layout with node identifier bits, otherwise all the peers share the
+    /// same identifier.
+    Quarantine,
+}
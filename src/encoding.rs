@@ -0,0 +1,99 @@
+use crate::error::{HlcError, HlcResult};
+
+/// Crockford's base32 alphabet.
+const ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Length of the encoded `u64` value.
+pub const ENCODED_LEN: usize = 13;
+
+/// Encodes the value using fixed-width Crockford's base32.
+///
+/// The most significant digit goes first, so the lexicographical order of the
+/// encoded strings matches the numerical order of the values.
+pub fn encode(value: u64) -> [u8; ENCODED_LEN] {
+    let mut buf = [0; ENCODED_LEN];
+    for (i, c) in buf.iter_mut().rev().enumerate() {
+        *c = ALPHABET[((value >> (i * 5)) & 0x1f) as usize];
+    }
+    buf
+}
+
+/// Decodes the value encoded with fixed-width Crockford's base32.
+///
+/// Decoding is case-insensitive, and `I`, `L` and `O` are accepted as aliases
+/// of `1`, `1` and `0`, respectively.
+pub fn decode(s: &str) -> HlcResult<u64> {
+    if s.len() != ENCODED_LEN {
+        return Err(HlcError::InvalidEncodingLength(s.len(), ENCODED_LEN));
+    }
+
+    let mut value = 0u64;
+    for (i, c) in s.chars().enumerate() {
+        let digit = match c.to_ascii_uppercase() {
+            c @ '0'..='9' => c as u64 - '0' as u64,
+            'O' => 0,
+            'I' | 'L' => 1,
+            c @ 'A'..='Z' => match ALPHABET.iter().position(|&a| a as char == c) {
+                Some(digit) => digit as u64,
+                None => return Err(HlcError::InvalidEncodingChar(c, i)),
+            },
+            _ => return Err(HlcError::InvalidEncodingChar(c, i)),
+        };
+        // The leading digit carries only 4 bits (13 * 5 = 65).
+        if i == 0 && digit > 0xf {
+            return Err(HlcError::InvalidEncodingChar(c, i));
+        }
+        value = (value << 5) | digit;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for value in [0, 1, 31, 32, 12345 << 22 | 67890, u64::MAX - 1, u64::MAX] {
+            let encoded = encode(value);
+            assert_eq!(decode(std::str::from_utf8(&encoded).unwrap()), Ok(value));
+        }
+        assert_eq!(&encode(0), b"0000000000000");
+        assert_eq!(&encode(u64::MAX), b"FZZZZZZZZZZZZ");
+    }
+
+    #[test]
+    fn sortable() {
+        let values = [0, 1, 31, 32, 1 << 22, 1 << 40, u64::MAX];
+        for w in values.windows(2) {
+            assert!(encode(w[0]) < encode(w[1]));
+        }
+    }
+
+    #[test]
+    fn decode_aliases() {
+        assert_eq!(decode("000000000001f"), Ok(0x2f));
+        assert_eq!(decode("OOOOOOOOOOOIL"), Ok(0x21));
+    }
+
+    #[test]
+    fn decode_errors() {
+        assert_eq!(
+            decode("000"),
+            Err(HlcError::InvalidEncodingLength(3, ENCODED_LEN))
+        );
+        assert_eq!(
+            decode("00000000000U0"),
+            Err(HlcError::InvalidEncodingChar('U', 11))
+        );
+        assert_eq!(
+            decode("000000#000000"),
+            Err(HlcError::InvalidEncodingChar('#', 6))
+        );
+        // Leading digit overflows 64 bits.
+        assert_eq!(
+            decode("G000000000000"),
+            Err(HlcError::InvalidEncodingChar('G', 0))
+        );
+    }
+}
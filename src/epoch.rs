@@ -1,19 +1,53 @@
-use crate::error::{HlcError, HlcResult};
+use {
+    crate::error::{HlcError, HlcResult},
+    std::{fmt::Debug, hash::Hash, marker::PhantomData},
+};
 
 /// Pre-calculated custom epoch.
 ///
 /// 2024-01-01 00:00:00 UTC in milliseconds since Unix epoch
 pub const EPOCH: i64 = 1_704_067_200_000;
 
-/// Timestamps in milliseconds since a custom epoch (2024-01-01 00:00:00 UTC).
+/// Epoch against which the physical time of HLC timestamps is encoded.
+///
+/// Timestamps carry their epoch as a type parameter, so timestamps encoded
+/// against different epochs cannot be mixed up. Use
+/// [`HlcTimestampOf::to_epoch()`](crate::HlcTimestampOf::to_epoch) to
+/// re-encode a timestamp against another epoch.
+///
+/// Normally, there is no need to implement this trait, as [`EpochAt`] covers
+/// any epoch, e.g. `EpochAt<1_546_300_800_000>` for 2019-01-01.
+pub trait Epoch:
+    Debug + Default + Clone + Copy + PartialEq + Eq + PartialOrd + Ord + Hash + Send + Sync + 'static
+{
+    /// Start of the epoch in milliseconds since the Unix epoch.
+    const UNIX_MILLIS: i64;
+}
+
+/// Epoch starting at `UNIX_MILLIS` milliseconds since the Unix epoch.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct EpochAt<const UNIX_MILLIS: i64>;
+
+impl<const UNIX_MILLIS: i64> Epoch for EpochAt<UNIX_MILLIS> {
+    const UNIX_MILLIS: i64 = UNIX_MILLIS;
+}
+
+/// Default epoch: 2024-01-01 00:00:00 UTC.
+pub type DefaultEpoch = EpochAt<EPOCH>;
+
+/// Unix epoch: 1970-01-01 00:00:00 UTC.
+pub type UnixEpoch = EpochAt<0>;
+
+/// Timestamps in milliseconds since a custom epoch (2024-01-01 00:00:00 UTC by
+/// default).
 #[derive(Debug)]
-pub struct CustomEpochTimestamp(u64);
+pub(crate) struct CustomEpochTimestamp<E: Epoch = DefaultEpoch>(u64, PhantomData<E>);
 
-impl CustomEpochTimestamp {
+impl<E: Epoch> CustomEpochTimestamp<E> {
     /// Creates a new `CustomEpochTimestamp` from the given milliseconds since
     /// the custom epoch.
     pub fn from_millis(ms: u64) -> Self {
-        Self(ms)
+        Self(ms, PhantomData)
     }
 
     /// Returns the stored timestamp in milliseconds since the custom epoch.
@@ -24,16 +58,19 @@ impl CustomEpochTimestamp {
     /// Creates a new `CustomEpochTimestamp` from the given Unix timestamp in
     /// milliseconds.
     pub fn from_unix_timestamp(unix_timestamp: i64) -> HlcResult<Self> {
-        if unix_timestamp < EPOCH {
-            return Err(HlcError::TimestampBelowMin(unix_timestamp, EPOCH));
+        if unix_timestamp < E::UNIX_MILLIS {
+            return Err(HlcError::TimestampBelowMin(unix_timestamp, E::UNIX_MILLIS));
         }
-        Ok(Self::from_millis((unix_timestamp - EPOCH) as u64))
+        Ok(Self::from_millis(unix_timestamp.abs_diff(E::UNIX_MILLIS)))
     }
 
     /// Returns the timestamp in milliseconds since the Unix epoch for a given
     /// number of milliseconds since the custom epoch.
+    ///
+    /// Milliseconds must not exceed the maximum physical time of the layout,
+    /// which is guaranteed to fit into `i64` along with the epoch.
     pub fn to_unix_timestamp(ms: u64) -> i64 {
-        ms as i64 + EPOCH
+        ms as i64 + E::UNIX_MILLIS
     }
 }
 
@@ -59,13 +96,40 @@ mod tests {
     #[test]
     fn conversion_to_and_from_unix_timestamp() {
         let unix_ts = 1704067200123; // 2024-01-01 00:00:00.123 UTC
-        let custom_ts = CustomEpochTimestamp::from_unix_timestamp(unix_ts).unwrap();
+        let custom_ts = CustomEpochTimestamp::<DefaultEpoch>::from_unix_timestamp(unix_ts).unwrap();
 
         // Check milliseconds from custom epoch
         assert_eq!(custom_ts.millis(), 123);
 
         // Convert back to Unix timestamp
-        let back_to_unix = CustomEpochTimestamp::to_unix_timestamp(custom_ts.millis());
+        let back_to_unix =
+            CustomEpochTimestamp::<DefaultEpoch>::to_unix_timestamp(custom_ts.millis());
         assert_eq!(back_to_unix, unix_ts);
     }
+
+    #[test]
+    fn user_selected_epoch() {
+        // 2019-01-01 00:00:00 UTC
+        type Epoch2019 = EpochAt<1_546_300_800_000>;
+        let expected_epoch = Utc
+            .with_ymd_and_hms(2019, 1, 1, 0, 0, 0)
+            .unwrap()
+            .timestamp_millis();
+        assert_eq!(Epoch2019::UNIX_MILLIS, expected_epoch);
+
+        // Timestamps before the default epoch are accepted.
+        let unix_ts = expected_epoch + 123;
+        let custom_ts = CustomEpochTimestamp::<Epoch2019>::from_unix_timestamp(unix_ts).unwrap();
+        assert_eq!(custom_ts.millis(), 123);
+        assert_eq!(
+            CustomEpochTimestamp::<Epoch2019>::to_unix_timestamp(custom_ts.millis()),
+            unix_ts
+        );
+
+        // Timestamps before the selected epoch are still rejected.
+        assert_eq!(
+            CustomEpochTimestamp::<Epoch2019>::from_unix_timestamp(expected_epoch - 1).unwrap_err(),
+            HlcError::TimestampBelowMin(expected_epoch - 1, expected_epoch)
+        );
+    }
 }
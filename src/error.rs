@@ -6,20 +6,95 @@ pub enum HlcError {
     OutOfRangeTimestamp,
 
     /// Drift is too large.
-    #[error("Drift exeeded the maximum allowed: {0} > {1}")]
-    DriftTooLarge(usize, usize),
+    ///
+    /// Contains the incoming timestamp and the wall-clock time (both are Unix
+    /// timestamps in milliseconds, or in nanoseconds for 128-bit timestamps),
+    /// the skew between them (positive if the incoming timestamp is ahead of
+    /// the wall-clock time, negative if it lags behind) and the maximum skew
+    /// allowed in that direction.
+    #[error(
+        "Drift exceeded the maximum allowed: incoming timestamp {0}, wall-clock time {1}, skew \
+         {2} (max {3})"
+    )]
+    DriftTooLarge(i64, i64, i64, usize),
+
+    /// Sender of the incoming timestamp is quarantined.
+    ///
+    /// Contains the node identifier of the sender, see
+    /// [`DriftPolicy::Quarantine`](crate::config::DriftPolicy::Quarantine).
+    #[error("Peer is quarantined: node {0}")]
+    PeerQuarantined(u64),
 
     /// Physical time exceeds maximum value.
+    ///
+    /// Contains the physical time and the maximum value supported by the
+    /// layout and epoch (both are Unix timestamps in milliseconds, or in
+    /// nanoseconds for 128-bit timestamps).
     #[error("Physical time exceeds maximum value: {0} > {1}")]
-    PhysicalTimeExceedsMax(i64, u64),
+    PhysicalTimeExceedsMax(i64, i64),
 
     /// Logical clock exceeds maximum value.
+    ///
+    /// Contains the requested logical clock count and the maximum value
+    /// supported by the layout. When reported by
+    /// [`HlcGenerator::next_timestamps()`](crate::HlcGenerator::next_timestamps),
+    /// too many timestamps have been requested within a single millisecond.
     #[error("Logical clock exceeds maximum value: {0} > {1}")]
     LogicalClockExceedsMax(u64, u64),
 
+    /// Node identifier exceeds maximum value.
+    #[error("Node identifier exceeds maximum value: {0} > {1}")]
+    NodeIdExceedsMax(u64, u64),
+
+    /// Logical clock of the generator overflowed.
+    ///
+    /// Contains the physical time of the generator and the wall-clock time
+    /// (both are Unix timestamps in milliseconds). If the wall-clock time is
+    /// behind the physical time, the clock has likely been set back, otherwise
+    /// too many timestamps have been requested within a single millisecond.
+    #[error("Logical clock overflow: physical time {0}, wall-clock time {1}")]
+    LogicalClockOverflow(i64, i64),
+
     /// Timestamp is below the minimum value.
+    ///
+    /// Contains the timestamp and the start of the epoch (both are Unix
+    /// timestamps in milliseconds, or in nanoseconds for 128-bit timestamps).
+    /// When reported by the generator, the wall-clock time precedes the
+    /// epoch, i.e. the clock is likely misset.
     #[error("Timestamp is below the minimum value: {0} < {1}")]
     TimestampBelowMin(i64, i64),
+
+    /// Encoded timestamp has unexpected length.
+    #[error("Invalid encoded timestamp length: {0} != {1}")]
+    InvalidEncodingLength(usize, usize),
+
+    /// Encoded timestamp contains a character outside of the alphabet.
+    #[error("Invalid character {0:?} at position {1} of encoded timestamp")]
+    InvalidEncodingChar(char, usize),
+
+    /// Date-time part of the timestamp cannot be parsed.
+    #[error("Invalid date-time: {0}")]
+    InvalidDateTime(chrono::ParseError),
+
+    /// Numeric part of the timestamp cannot be parsed.
+    #[error("Invalid number: {0}")]
+    InvalidNumber(std::num::ParseIntError),
+
+    /// Timestamp string is malformed.
+    #[error("Invalid timestamp format: {0}")]
+    InvalidFormat(String),
+
+    /// Generator configuration is invalid.
+    ///
+    /// Contains the description of the problem.
+    #[error("Invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    /// High-water mark cannot be loaded or stored.
+    ///
+    /// Contains the description of the underlying failure.
+    #[error("Persistence failure: {0}")]
+    Persistence(String),
 }
 
 /// HLC result type.
@@ -0,0 +1,507 @@
+use {
+    crate::{
+        config::{DriftPolicy, HlcConfig, OverflowPolicy},
+        error::{HlcError, HlcResult},
+        source::{ClockSource, ManualClock, UtcClock},
+    },
+    portable_atomic::AtomicU128,
+    std::{cmp::Ordering, sync::atomic::Ordering as AtomicOrdering},
+};
+
+/// Number of bits to represent logical clock counter.
+const LC_BITS: u8 = 32;
+
+/// Number of bits to represent node identifier.
+const NODE_BITS: u8 = 32;
+
+/// 128-bit Hybrid logical clock (HLC) timestamp.
+///
+/// The timestamp is represented as a 128-bit unsigned integer. The upper 64
+/// bits represent the physical time in nanoseconds since the Unix epoch, the
+/// next 32 bits represent the logical clock count, and the lowest 32 bits
+/// represent the identifier of the node that generated the timestamp (zero,
+/// unless set on the generator).
+///
+/// Use this type, when the millisecond granularity of
+/// [`HlcTimestamp`](crate::HlcTimestamp) is too coarse.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Hlc128Timestamp(u128);
+
+impl std::fmt::Display for Hlc128Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Hlc128Timestamp {{ timestamp: {}, count: {}, node: {} }}",
+            self.timestamp(),
+            self.count(),
+            self.node()
+        )
+    }
+}
+
+impl TryFrom<u128> for Hlc128Timestamp {
+    type Error = HlcError;
+
+    fn try_from(value: u128) -> Result<Self, Self::Error> {
+        let (pt, lc, node) = Self::split_raw(value);
+        let pt =
+            i64::try_from(pt).map_err(|_| HlcError::PhysicalTimeExceedsMax(i64::MAX, i64::MAX))?;
+        Self::from_parts_with_node(pt, lc, node)
+    }
+}
+
+impl Hlc128Timestamp {
+    /// Maximum value for physical time (in nanoseconds since the Unix epoch).
+    pub const PT_MAX: u64 = i64::MAX as u64;
+
+    /// Maximum value for logical clock.
+    pub const LC_MAX: u64 = (1 << LC_BITS) - 1;
+
+    /// Maximum value for node identifier.
+    pub const NODE_MAX: u64 = (1 << NODE_BITS) - 1;
+
+    /// Creates a new HLC timestamp from incoming physical time (in
+    /// nanoseconds since the Unix epoch).
+    pub fn new(unix_timestamp_nanos: i64) -> HlcResult<Self> {
+        Self::from_parts(unix_timestamp_nanos, 0)
+    }
+
+    /// Creates a new HLC timestamp from the given physical time (in
+    /// nanoseconds since the Unix epoch) and logical clock count.
+    pub fn from_parts(pt: i64, lc: u64) -> HlcResult<Self> {
+        Self::from_parts_with_node(pt, lc, 0)
+    }
+
+    /// Creates a new HLC timestamp from the given physical time (in
+    /// nanoseconds since the Unix epoch), logical clock count and node
+    /// identifier.
+    pub fn from_parts_with_node(pt: i64, lc: u64, node: u64) -> HlcResult<Self> {
+        if pt < 0 {
+            return Err(HlcError::TimestampBelowMin(pt, 0));
+        }
+        if lc > Self::LC_MAX {
+            return Err(HlcError::LogicalClockExceedsMax(lc, Self::LC_MAX));
+        }
+        if node > Self::NODE_MAX {
+            return Err(HlcError::NodeIdExceedsMax(node, Self::NODE_MAX));
+        }
+        Ok(Self(Self::combine(pt as u64, lc, node)))
+    }
+
+    /// Unix timestamp in nanoseconds.
+    pub fn timestamp(&self) -> i64 {
+        Self::split_raw(self.0).0 as i64
+    }
+
+    /// Logical clock count.
+    pub fn count(&self) -> u64 {
+        Self::split_raw(self.0).1
+    }
+
+    /// Identifier of the node that generated the timestamp.
+    pub fn node(&self) -> u64 {
+        Self::split_raw(self.0).2
+    }
+
+    /// Returns the physical time and logical clock count as a tuple.
+    pub fn parts(&self) -> (i64, u64) {
+        (self.timestamp(), self.count())
+    }
+
+    /// Returns the raw `u128` value of the HLC ID.
+    pub fn as_u128(&self) -> u128 {
+        self.0
+    }
+
+    /// Splits raw `u128` data into physical time, logical clock count and
+    /// node identifier parts.
+    fn split_raw(value: u128) -> (u64, u64, u64) {
+        let pt = (value >> (LC_BITS + NODE_BITS)) as u64;
+        let lc = ((value >> NODE_BITS) as u64) & Self::LC_MAX;
+        let node = (value as u64) & Self::NODE_MAX;
+        (pt, lc, node)
+    }
+
+    /// Combines raw parts into `u128` data.
+    fn combine(pt: u64, lc: u64, node: u64) -> u128 {
+        ((pt as u128) << (LC_BITS + NODE_BITS)) | ((lc as u128) << NODE_BITS) | node as u128
+    }
+}
+
+/// Atomic storage of [`Hlc128Timestamp`].
+///
+/// Relies on native 128-bit atomics where available, and falls back to a
+/// lock-based implementation otherwise.
+#[derive(Debug)]
+struct Hlc128AtomicTimestamp(AtomicU128);
+
+impl From<Hlc128Timestamp> for Hlc128AtomicTimestamp {
+    fn from(ts: Hlc128Timestamp) -> Self {
+        Self(AtomicU128::new(ts.0))
+    }
+}
+
+impl Hlc128AtomicTimestamp {
+    /// Sets the physical time and logical clock count.
+    ///
+    /// Mirrors `HlcAtomicTimestamp::update()`: the closure gets the current
+    /// physical time and logical clock count and must return the new values
+    /// for both. The node identifier is never changed.
+    fn update<F>(&self, new_values: F) -> HlcResult<Hlc128Timestamp>
+    where
+        F: Fn(i64, u64) -> HlcResult<(i64, u64)>,
+    {
+        loop {
+            let current = self.0.load(AtomicOrdering::Acquire);
+            let (current_pt, current_lc, node) = Hlc128Timestamp::split_raw(current);
+
+            // Obtain new values for physical time and logical clock count.
+            let (pt, lc) = new_values(current_pt as i64, current_lc)?;
+
+            let new_combined = Hlc128Timestamp::from_parts_with_node(pt, lc, node)?.0;
+
+            if self
+                .0
+                .compare_exchange(
+                    current,
+                    new_combined,
+                    AtomicOrdering::AcqRel,
+                    AtomicOrdering::Acquire,
+                )
+                .is_ok()
+            {
+                return Ok(Hlc128Timestamp(new_combined));
+            }
+        }
+    }
+
+    /// Creates a new HLC timestamp snapshot.
+    fn snapshot(&self) -> Hlc128Timestamp {
+        Hlc128Timestamp(self.0.load(AtomicOrdering::Acquire))
+    }
+}
+
+/// 128-bit Hybrid Logical Clock (HLC) generator.
+///
+/// Same as [`HlcGenerator`](crate::HlcGenerator), but produces
+/// [`Hlc128Timestamp`]s with nanosecond granularity (see
+/// [`ClockSource::current_timestamp_nanos()`]).
+pub struct Hlc128Generator<S: ClockSource = UtcClock> {
+    /// The last timestamp generated by the clock.
+    state: Hlc128AtomicTimestamp,
+
+    /// The maximum drift (in nanoseconds) allowed between the physical clock
+    /// and the wall-clock time.
+    max_drift: usize,
+
+    /// The maximum lag (in nanoseconds) allowed between the wall-clock time
+    /// and the incoming timestamps.
+    max_lag: usize,
+
+    /// The timestamp provider used to get the current timestamp.
+    clock: S,
+}
+
+impl Default for Hlc128Generator<UtcClock> {
+    /// Creates a new HLC clock without any drift.
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl Hlc128Generator<UtcClock> {
+    /// Creates a new HLC clock with the specified maximum drift (in
+    /// milliseconds).
+    ///
+    /// See [`HlcGenerator::new()`](crate::HlcGenerator::new) for details.
+    pub fn new(max_drift: usize) -> Self {
+        Self::with_max_drift(max_drift)
+    }
+}
+
+impl Hlc128Generator<ManualClock> {
+    /// Creates a new manual HLC clock with the specified maximum drift (in
+    /// milliseconds).
+    ///
+    /// Useful for testing purposes, where manual timestamps are used.
+    pub fn manual(max_drift: usize) -> Self {
+        Self::with_max_drift(max_drift)
+    }
+
+    /// Sets the current timestamp (in milliseconds since the Unix epoch) of
+    /// the underlying manual clock.
+    pub fn set_current_timestamp(&self, timestamp: i64) {
+        self.clock.set_current_timestamp(timestamp);
+    }
+}
+
+impl<S: ClockSource + Default> Hlc128Generator<S> {
+    /// Creates a new HLC clock with the specified maximum drift (in
+    /// milliseconds).
+    pub fn with_max_drift(max_drift: usize) -> Self {
+        Self::with_config(HlcConfig {
+            max_drift,
+            ..Default::default()
+        })
+        .expect("default config is always valid")
+    }
+
+    /// Creates a new HLC clock with the specified configuration.
+    ///
+    /// The maximum drift and lag are given in milliseconds, and the node
+    /// identifier must fit into 32 bits. Only the default policies are
+    /// supported: drift is always rejected, and the logical clock is not
+    /// expected to overflow at nanosecond granularity.
+    /// [`HlcError::InvalidConfig`] is returned for any other policy.
+    pub fn with_config(config: HlcConfig) -> HlcResult<Self> {
+        Self::with_clock(S::default(), config)
+    }
+}
+
+impl<S: ClockSource> Hlc128Generator<S> {
+    /// Creates a new HLC clock with the specified clock source and
+    /// configuration.
+    ///
+    /// See [`HlcGenerator::with_clock()`](crate::HlcGenerator::with_clock) for
+    /// details, only the default policies are supported (see
+    /// [`with_config()`](Hlc128Generator::with_config)).
+    pub fn with_clock(clock: S, config: HlcConfig) -> HlcResult<Self> {
+        if config.drift_policy != DriftPolicy::Reject {
+            return Err(HlcError::InvalidConfig(
+                "128-bit generator only supports the reject drift policy".into(),
+            ));
+        }
+        if config.overflow_policy != OverflowPolicy::default() {
+            return Err(HlcError::InvalidConfig(
+                "128-bit generator only supports the default overflow policy".into(),
+            ));
+        }
+        let state = Hlc128Timestamp::from_parts_with_node(
+            clock.current_timestamp_nanos().max(0),
+            0,
+            config.node_id,
+        )?
+        .into();
+        Ok(Self {
+            state,
+            max_drift: config.max_drift.saturating_mul(1_000_000),
+            max_lag: config.max_lag.saturating_mul(1_000_000),
+            clock,
+        })
+    }
+
+    /// Identifier of the node, embedded into every generated timestamp.
+    pub fn node_id(&self) -> u64 {
+        self.state.snapshot().node()
+    }
+
+    /// Current timestamp.
+    ///
+    /// Use [`next_timestamp()`](Hlc128Generator::next_timestamp) to get the
+    /// timestamp for local or send events.
+    pub fn timestamp(&self) -> Hlc128Timestamp {
+        self.state.snapshot()
+    }
+
+    /// Timestamp for the local or send event.
+    pub fn next_timestamp(&self) -> Option<Hlc128Timestamp> {
+        let timestamp = self.clock.current_timestamp_nanos();
+
+        self.state
+            .update(move |pt, lc| {
+                // Update the physical time and increment the logical count.
+                if pt >= timestamp {
+                    Ok((pt, lc + 1))
+                } else {
+                    Ok((timestamp, 0))
+                }
+            })
+            .ok()
+    }
+
+    /// Adjust the clock based on incoming timestamp.
+    ///
+    /// See [`HlcGenerator::update()`](crate::HlcGenerator::update) for
+    /// details. Timestamps and skew reported in errors are measured in
+    /// nanoseconds.
+    pub fn update(&self, incoming_state: &Hlc128Timestamp) -> HlcResult<Hlc128Timestamp> {
+        let max_drift = self.max_drift;
+        let timestamp = self.clock.current_timestamp_nanos();
+        let (incoming_pt, incoming_lc) = incoming_state.parts();
+
+        let skew = incoming_pt.saturating_sub(timestamp);
+        if self.max_lag > 0 && skew < 0 && skew.unsigned_abs() > self.max_lag as u64 {
+            return Err(HlcError::DriftTooLarge(
+                incoming_pt,
+                timestamp,
+                skew,
+                self.max_lag,
+            ));
+        }
+
+        self.state.update(move |pt, lc| {
+            // Physical clock is ahead of both the incoming timestamp and the current state.
+            if timestamp > incoming_pt && timestamp > pt {
+                return Ok((timestamp, 0));
+            }
+
+            match incoming_pt.cmp(&pt) {
+                // Incoming timestamp is ahead of the current state.
+                Ordering::Greater => {
+                    if max_drift > 0 && usize::try_from(skew).is_ok_and(|drift| drift > max_drift) {
+                        return Err(HlcError::DriftTooLarge(
+                            incoming_pt,
+                            timestamp,
+                            skew,
+                            max_drift,
+                        ));
+                    }
+                    Ok((incoming_pt, incoming_lc + 1))
+                }
+                // Incoming timestamp is behind the current state.
+                Ordering::Less => Ok((pt, lc + 1)),
+                // Timestamps are equal, so we need to use the maximum logical count for update.
+                Ordering::Equal => Ok((pt, lc.max(incoming_lc) + 1)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::epoch::EPOCH, std::sync::Arc};
+
+    const MS: i64 = 1_000_000;
+
+    #[test]
+    fn parts() {
+        let ts = Hlc128Timestamp::from_parts_with_node(EPOCH * MS + 123, 456, 789).unwrap();
+        assert_eq!(ts.parts(), (EPOCH * MS + 123, 456));
+        assert_eq!(ts.node(), 789);
+        assert_eq!(Hlc128Timestamp::try_from(ts.as_u128()), Ok(ts));
+
+        assert_eq!(
+            Hlc128Timestamp::from_parts(-1, 0),
+            Err(HlcError::TimestampBelowMin(-1, 0))
+        );
+        assert_eq!(
+            Hlc128Timestamp::from_parts(0, 1 << 32),
+            Err(HlcError::LogicalClockExceedsMax(1 << 32, u32::MAX as u64))
+        );
+        assert_eq!(
+            Hlc128Timestamp::try_from(u128::MAX),
+            Err(HlcError::PhysicalTimeExceedsMax(i64::MAX, i64::MAX))
+        );
+    }
+
+    #[test]
+    fn next_and_update() {
+        let g: Hlc128Generator<ManualClock> = Hlc128Generator::with_config(HlcConfig {
+            max_drift: 1,
+            node_id: 7,
+            ..Default::default()
+        })
+        .unwrap();
+        g.set_current_timestamp(EPOCH);
+
+        let t1 = g.next_timestamp().unwrap();
+        assert_eq!(t1.parts(), (EPOCH * MS, 1));
+        assert_eq!(t1.node(), 7);
+
+        // Incoming timestamp ahead, within drift.
+        let t2 = Hlc128Timestamp::from_parts(EPOCH * MS + MS, 5).unwrap();
+        assert_eq!(g.update(&t2).unwrap().parts(), (EPOCH * MS + MS, 6));
+
+        // Incoming timestamp ahead, drift exceeded.
+        let t3 = Hlc128Timestamp::from_parts(EPOCH * MS + MS + 1, 5).unwrap();
+        assert_eq!(
+            g.update(&t3),
+            Err(HlcError::DriftTooLarge(
+                EPOCH * MS + MS + 1,
+                EPOCH * MS,
+                MS + 1,
+                MS as usize
+            ))
+        );
+
+        // Incoming timestamp behind, lag is not checked.
+        let t5 = Hlc128Timestamp::from_parts(0, 5).unwrap();
+        assert_eq!(g.update(&t5).unwrap().parts(), (EPOCH * MS + MS, 7));
+
+        // Physical clock moves ahead.
+        g.set_current_timestamp(EPOCH + 2);
+        let t4 = g.next_timestamp().unwrap();
+        assert_eq!(t4.parts(), (EPOCH * MS + 2 * MS, 0));
+        assert!(t4 > t1);
+    }
+
+    #[test]
+    fn max_lag() {
+        let g: Hlc128Generator<ManualClock> = Hlc128Generator::with_config(HlcConfig {
+            max_lag: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        g.set_current_timestamp(EPOCH);
+
+        let t1 = Hlc128Timestamp::from_parts(EPOCH * MS - MS, 0).unwrap();
+        assert!(g.update(&t1).is_ok());
+        let t2 = Hlc128Timestamp::from_parts(EPOCH * MS - MS - 1, 0).unwrap();
+        assert_eq!(
+            g.update(&t2),
+            Err(HlcError::DriftTooLarge(
+                EPOCH * MS - MS - 1,
+                EPOCH * MS,
+                -MS - 1,
+                MS as usize
+            ))
+        );
+    }
+
+    #[test]
+    fn unsupported_policies() {
+        for config in [
+            HlcConfig {
+                drift_policy: DriftPolicy::Clamp,
+                ..Default::default()
+            },
+            HlcConfig {
+                overflow_policy: OverflowPolicy::Wait,
+                ..Default::default()
+            },
+        ] {
+            assert!(matches!(
+                Hlc128Generator::<ManualClock>::with_config(config),
+                Err(HlcError::InvalidConfig(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn concurrent_next_timestamp() {
+        let g = Arc::new(Hlc128Generator::default());
+        let handles = (0..10)
+            .map(|_| {
+                let g = Arc::clone(&g);
+                std::thread::spawn(move || {
+                    (0..1000)
+                        .map(|_| g.next_timestamp().unwrap())
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut timestamps = vec![];
+        for handle in handles {
+            let thread_timestamps = handle.join().unwrap();
+            // Timestamps are monotonic within a thread.
+            assert!(thread_timestamps.windows(2).all(|w| w[0] < w[1]));
+            timestamps.extend(thread_timestamps);
+        }
+
+        // Timestamps are unique across threads.
+        timestamps.sort();
+        timestamps.dedup();
+        assert_eq!(timestamps.len(), 10 * 1000);
+    }
+}
@@ -0,0 +1,52 @@
+use std::{fmt::Debug, hash::Hash};
+
+/// Bit layout of the `u64` backing an HLC timestamp.
+///
+/// The upper `PT_BITS` bits represent the physical time in milliseconds since
+/// a custom epoch, the next `LC_BITS` bits represent the logical clock count,
+/// and the lowest `NODE_BITS` bits represent the node identifier. Physical
+/// time and logical clock must be non-empty, all parts must fit into 64 bits,
+/// and the maximum physical time must be representable as `i64` Unix
+/// timestamp, given the epoch (this is checked at compile time, whenever a
+/// timestamp with the layout is used).
+///
+/// Since the node identifier occupies the lowest bits, timestamps are ordered
+/// by their HLC parts first, with the node identifier acting as a
+/// tie-breaker. Timestamps generated by nodes with different identifiers never
+/// collide.
+///
+/// Normally, there is no need to implement this trait, as [`BitLayout`]
+/// covers any split of the bits, e.g. `BitLayout<48, 16>` for a longer time
+/// horizon, `BitLayout<40, 24>` for high-burst workloads, or
+/// `BitLayout<42, 12, 10>` for TSID-style identifiers of up to 1024 nodes.
+pub trait Layout:
+    Debug + Default + Clone + Copy + PartialEq + Eq + PartialOrd + Ord + Hash + Send + Sync + 'static
+{
+    /// Number of bits to represent physical time in milliseconds since custom
+    /// epoch.
+    const PT_BITS: u8;
+
+    /// Number of bits to represent logical clock counter.
+    const LC_BITS: u8;
+
+    /// Number of bits to represent node identifier.
+    const NODE_BITS: u8 = 0;
+}
+
+/// Layout with `PT_BITS` bits of physical time, `LC_BITS` bits of logical
+/// clock count and `NODE_BITS` bits of node identifier.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BitLayout<const PT_BITS: u8, const LC_BITS: u8, const NODE_BITS: u8 = 0>;
+
+impl<const PT_BITS: u8, const LC_BITS: u8, const NODE_BITS: u8> Layout
+    for BitLayout<PT_BITS, LC_BITS, NODE_BITS>
+{
+    const PT_BITS: u8 = PT_BITS;
+    const LC_BITS: u8 = LC_BITS;
+    const NODE_BITS: u8 = NODE_BITS;
+}
+
+/// Default layout: 42 bits of physical time (around 139 years of
+/// milliseconds) and 22 bits of logical clock (around 4M of items per
+/// millisecond).
+pub type DefaultLayout = BitLayout<42, 22>;
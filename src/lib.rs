@@ -1,29 +1,105 @@
 #![doc = include_str!("../README.md")]
 
-mod epoch;
+mod builder;
+pub mod config;
+mod encoding;
+pub mod epoch;
 pub mod error;
+mod hlc128;
+pub mod layout;
+pub mod persist;
+#[cfg(feature = "serde")]
+mod serde;
+#[cfg(feature = "sim")]
+pub mod sim;
+pub mod skew;
 pub mod source;
+#[cfg(feature = "proptest")]
+pub mod strategy;
+pub mod telemetry;
 mod timestamp;
 
-pub use timestamp::HlcTimestamp;
 use {
     crate::{
+        config::{DriftPolicy, HlcConfig, OverflowPolicy},
+        epoch::{DefaultEpoch, Epoch},
         error::{HlcError, HlcResult},
+        layout::{DefaultLayout, Layout},
+        persist::{HighWaterMark, Persistence},
+        skew::{PeerSkew, SkewTracker},
         source::{ClockSource, ManualClock, UtcClock},
+        telemetry::RegressionWatch,
+    },
+    parking_lot::RwLock,
+    std::{
+        cell::Cell,
+        cmp::Ordering,
+        collections::HashSet,
+        sync::atomic::{self, AtomicU64},
     },
-    std::cmp::Ordering,
     timestamp::HlcAtomicTimestamp,
 };
+pub use {
+    builder::HlcGeneratorBuilder,
+    hlc128::{Hlc128Generator, Hlc128Timestamp},
+    timestamp::{HlcTimestamp, HlcTimestampOf, HlcTimestampRange},
+};
+
+/// How far (in milliseconds) a batch of timestamps may run ahead of the
+/// wall-clock time with [`OverflowPolicy::BorrowNext`], if the drift check is
+/// disabled.
+const MAX_BORROWED_MS: i64 = 1000;
 
 /// Hybrid Logical Clock (HLC) generator.
-pub struct HlcGenerator<S: ClockSource = UtcClock> {
+///
+/// Generated timestamps use the bit layout `L` (see [`Layout`]), which
+/// defaults to 42 bits of physical time and 22 bits of logical clock, and
+/// encode physical time against the epoch `E` (see [`Epoch`]), which defaults
+/// to 2024-01-01.
+pub struct HlcGenerator<
+    S: ClockSource = UtcClock,
+    L: Layout = DefaultLayout,
+    E: Epoch = DefaultEpoch,
+> {
     /// The last timestamp generated by the clock.
-    state: HlcAtomicTimestamp,
+    state: HlcAtomicTimestamp<L, E>,
 
     /// The maximum drift (in milliseconds) allowed between the physical clock
     /// and the wall-clock time.
     max_drift: usize,
 
+    /// The maximum lag (in milliseconds) allowed between the wall-clock time
+    /// and the incoming timestamps.
+    max_lag: usize,
+
+    /// What to do when the maximum drift or lag is exceeded.
+    drift_policy: DriftPolicy,
+
+    /// Number of drift violations encountered.
+    drift_violations: AtomicU64,
+
+    /// Number of incoming timestamps clamped by the drift policy.
+    clamped: AtomicU64,
+
+    /// Node identifiers of the quarantined peers.
+    quarantined: RwLock<HashSet<u64>>,
+
+    /// Clock skew statistics of the peers, collected by `update_from()`.
+    skews: SkewTracker,
+
+    /// Watch for the wall-clock regressions (only with the `metrics` or
+    /// `tracing` feature).
+    regressions: RegressionWatch,
+
+    /// What to do when the logical clock overflows.
+    overflow_policy: OverflowPolicy,
+
+    /// Number of logical clock overflows encountered.
+    overflows: AtomicU64,
+
+    /// Persisted high-water mark, if persistence is enabled.
+    high_water_mark: Option<HighWaterMark>,
+
     /// The timestamp provider used to get the current timestamp.
     clock: S,
 }
@@ -46,6 +122,14 @@ impl HlcGenerator<UtcClock> {
     pub fn new(max_drift: usize) -> Self {
         Self::with_max_drift(max_drift)
     }
+
+    /// Creates a builder, which allows to set all the options of the
+    /// generator, including clock source, layout and epoch.
+    ///
+    /// See [`HlcGeneratorBuilder`] for details.
+    pub fn builder() -> HlcGeneratorBuilder {
+        HlcGeneratorBuilder::new()
+    }
 }
 
 impl HlcGenerator<ManualClock> {
@@ -55,101 +139,710 @@ impl HlcGenerator<ManualClock> {
     pub fn manual(max_drift: usize) -> Self {
         Self::with_max_drift(max_drift)
     }
+}
 
+impl<L: Layout, E: Epoch> HlcGenerator<ManualClock, L, E> {
+    /// Sets the current timestamp (in milliseconds since the Unix epoch) of
+    /// the underlying manual clock.
     pub fn set_current_timestamp(&self, timestamp: i64) {
         self.clock.set_current_timestamp(timestamp);
     }
 }
 
-impl<S: ClockSource> HlcGenerator<S> {
+impl<S: ClockSource + Default, L: Layout, E: Epoch> HlcGenerator<S, L, E> {
     /// Creates a new HLC clock with the specified maximum drift.
-    fn with_max_drift(max_drift: usize) -> Self {
-        let clock = S::default();
-        let state = HlcTimestamp::from_parts(clock.current_timestamp(), 0)
+    ///
+    /// Unlike [`new()`](HlcGenerator::new) and
+    /// [`manual()`](HlcGenerator::manual), works with any clock source, layout
+    /// and epoch:
+    ///
+    /// ```
+    /// use hlc_gen::{HlcGenerator, layout::BitLayout, source::UtcClock};
+    ///
+    /// // 48 bits of physical time, 16 bits of logical clock.
+    /// let g: HlcGenerator<UtcClock, BitLayout<48, 16>> = HlcGenerator::with_max_drift(1000);
+    /// let ts = g.next_timestamp().unwrap();
+    /// assert!(ts.count() <= u16::MAX as u64);
+    /// ```
+    ///
+    /// ```
+    /// use hlc_gen::{HlcGenerator, epoch::EpochAt, layout::DefaultLayout, source::ManualClock};
+    ///
+    /// // Epoch aligned to a product launch on 2025-01-01 00:00:00 UTC.
+    /// type LaunchEpoch = EpochAt<1_735_689_600_000>;
+    ///
+    /// let g: HlcGenerator<ManualClock, DefaultLayout, LaunchEpoch> = HlcGenerator::with_max_drift(0);
+    /// g.set_current_timestamp(1_746_057_600_000); // 2025-05-01 00:00:00 UTC
+    /// let ts = g.next_timestamp().unwrap();
+    /// assert_eq!(ts.parts(), (1_746_057_600_000, 0));
+    /// assert_eq!(ts.as_u64() >> 22, 1_746_057_600_000 - 1_735_689_600_000);
+    /// ```
+    pub fn with_max_drift(max_drift: usize) -> Self {
+        Self::with_config(HlcConfig {
+            max_drift,
+            ..Default::default()
+        })
+        .expect("default config is always valid")
+    }
+
+    /// Creates a new HLC clock with the specified configuration.
+    ///
+    /// An error is returned if the node identifier does not fit into the
+    /// layout.
+    ///
+    /// ```
+    /// use hlc_gen::{HlcGenerator, config::HlcConfig, layout::BitLayout, source::UtcClock};
+    ///
+    /// // TSID-style layout: 42 bits of physical time, 12 bits of logical
+    /// // clock, 10 bits of node identifier.
+    /// type Layout = BitLayout<42, 12, 10>;
+    ///
+    /// let g: HlcGenerator<UtcClock, Layout> = HlcGenerator::with_config(HlcConfig {
+    ///     node_id: 42,
+    ///     ..Default::default()
+    /// })
+    /// .unwrap();
+    /// assert_eq!(g.next_timestamp().unwrap().node(), 42);
+    /// ```
+    pub fn with_config(config: HlcConfig) -> HlcResult<Self> {
+        Self::init(config, S::default(), None, None)
+    }
+
+    /// Creates a new HLC clock with the specified configuration and
+    /// persistence of the high-water mark.
+    ///
+    /// The generator state is seeded with the persisted high-water mark (if it
+    /// is ahead of the wall-clock time), so that timestamps never go backwards
+    /// across restarts. Whenever issued timestamps reach the high-water mark,
+    /// it is moved `window` milliseconds ahead and persisted, i.e. the larger
+    /// the window, the less often the persistence is hit, but the further
+    /// ahead of the wall-clock time the generator may jump after a restart.
+    /// The window must be non-zero.
+    ///
+    /// ```
+    /// use hlc_gen::{HlcGenerator, config::HlcConfig, persist::FilePersistence};
+    ///
+    /// # let dir = tempfile::tempdir().unwrap();
+    /// # let path = dir.path().join("hlc");
+    /// let g: HlcGenerator =
+    ///     HlcGenerator::with_persistence(HlcConfig::default(), FilePersistence::new(&path), 1000)
+    ///         .unwrap();
+    /// let ts = g.next_timestamp().unwrap();
+    /// drop(g);
+    ///
+    /// // Restarted generator continues past the persisted high-water mark.
+    /// let g: HlcGenerator =
+    ///     HlcGenerator::with_persistence(HlcConfig::default(), FilePersistence::new(&path), 1000)
+    ///         .unwrap();
+    /// assert!(g.next_timestamp().unwrap() > ts);
+    /// ```
+    pub fn with_persistence(
+        config: HlcConfig,
+        persistence: impl Persistence + 'static,
+        window: u64,
+    ) -> HlcResult<Self> {
+        let high_water_mark = HighWaterMark::load(Box::new(persistence), window)?;
+        Self::init(config, S::default(), None, Some(high_water_mark))
+    }
+}
+
+impl<S: ClockSource, L: Layout, E: Epoch> HlcGenerator<S, L, E> {
+    /// Creates a new HLC clock with the specified clock source and
+    /// configuration.
+    ///
+    /// Unlike [`with_config()`](HlcGenerator::with_config), accepts a
+    /// pre-built clock source, so sources that need configuration (or are
+    /// shared with other components) can be used:
+    ///
+    /// ```
+    /// use {
+    ///     hlc_gen::{HlcGenerator, config::HlcConfig, source::ManualClock},
+    ///     std::sync::Arc,
+    /// };
+    ///
+    /// // Two generators driven by the same clock.
+    /// let clock = Arc::new(ManualClock::new(1_735_689_600_000));
+    /// let g1: HlcGenerator<_> =
+    ///     HlcGenerator::with_clock(clock.clone(), HlcConfig::default()).unwrap();
+    /// let g2: HlcGenerator<_> =
+    ///     HlcGenerator::with_clock(clock.clone(), HlcConfig::default()).unwrap();
+    ///
+    /// clock.set_current_timestamp(1_746_057_600_000);
+    /// assert_eq!(g1.next_timestamp().unwrap().timestamp(), 1_746_057_600_000);
+    /// assert_eq!(g2.next_timestamp().unwrap().timestamp(), 1_746_057_600_000);
+    /// ```
+    ///
+    /// An error is returned if the node identifier does not fit into the
+    /// layout.
+    pub fn with_clock(clock: S, config: HlcConfig) -> HlcResult<Self> {
+        Self::init(config, clock, None, None)
+    }
+
+    /// Creates a new HLC clock from its parts.
+    fn init(
+        config: HlcConfig,
+        clock: S,
+        initial_state: Option<HlcTimestampOf<L, E>>,
+        high_water_mark: Option<HighWaterMark>,
+    ) -> HlcResult<Self> {
+        let mut timestamp = clock.current_timestamp();
+        if let Some(hwm) = &high_water_mark {
+            // Nothing at or beyond the high-water mark has been issued.
+            timestamp = timestamp.max(hwm.value());
+        }
+        let state = HlcTimestampOf::from_parts(timestamp, 0)
             .unwrap_or_default()
-            .into();
-        Self {
+            .with_node(config.node_id)?;
+        let state = initial_state.map_or(state, |ts| ts.max(state)).into();
+        Ok(Self {
             state,
-            max_drift,
+            max_drift: config.max_drift,
+            max_lag: config.max_lag,
+            drift_policy: config.drift_policy,
+            drift_violations: AtomicU64::new(0),
+            clamped: AtomicU64::new(0),
+            quarantined: RwLock::new(HashSet::new()),
+            skews: SkewTracker::default(),
+            regressions: RegressionWatch::new(),
+            overflow_policy: config.overflow_policy,
+            overflows: AtomicU64::new(0),
+            high_water_mark,
             clock,
+        })
+    }
+
+    /// Identifier of the node, embedded into every generated timestamp.
+    pub fn node_id(&self) -> u64 {
+        self.state.snapshot().node()
+    }
+
+    /// Number of logical clock overflows encountered by
+    /// [`next_timestamp()`](HlcGenerator::next_timestamp).
+    ///
+    /// Counted regardless of the [`OverflowPolicy`], i.e. it is the number of
+    /// failed requests for [`OverflowPolicy::Error`], borrowed milliseconds for
+    /// [`OverflowPolicy::BorrowNext`], and waited requests for
+    /// [`OverflowPolicy::Wait`].
+    pub fn overflows(&self) -> u64 {
+        self.overflows.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Number of incoming timestamps, which exceeded the maximum drift or lag
+    /// in [`update()`](HlcGenerator::update).
+    ///
+    /// Counted regardless of the [`DriftPolicy`].
+    pub fn drift_violations(&self) -> u64 {
+        self.drift_violations.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Number of incoming timestamps clamped by [`DriftPolicy::Clamp`] in
+    /// [`update()`](HlcGenerator::update), i.e. the updated timestamps, which
+    /// are not ordered after the incoming ones.
+    pub fn clamped(&self) -> u64 {
+        self.clamped.load(atomic::Ordering::Relaxed)
+    }
+
+    /// Node identifiers of the peers quarantined by
+    /// [`DriftPolicy::Quarantine`], in ascending order.
+    pub fn quarantined_peers(&self) -> Vec<u64> {
+        let mut peers = self.quarantined.read().iter().copied().collect::<Vec<_>>();
+        peers.sort_unstable();
+        peers
+    }
+
+    /// Releases the peer from quarantine, so that its timestamps are accepted
+    /// again.
+    ///
+    /// Returns `false` if the peer has not been quarantined.
+    pub fn release_peer(&self, node_id: u64) -> bool {
+        self.quarantined.write().remove(&node_id)
+    }
+
+    /// Clock skew statistics of the peer, collected by
+    /// [`update_from()`](HlcGenerator::update_from).
+    ///
+    /// `None` is returned if no timestamps have been received from the peer.
+    pub fn peer_skew(&self, peer_id: u64) -> Option<PeerSkew> {
+        self.skews.get(peer_id)
+    }
+
+    /// Clock skew statistics of all the peers, ordered by the peer identifier.
+    pub fn peer_skews(&self) -> Vec<(u64, PeerSkew)> {
+        self.skews.all()
+    }
+
+    /// Drops the clock skew statistics of the peer (e.g. once it leaves the
+    /// cluster), returning them.
+    pub fn forget_peer(&self, peer_id: u64) -> Option<PeerSkew> {
+        self.skews.remove(peer_id)
+    }
+
+    /// Persisted high-water mark (in milliseconds since the Unix epoch).
+    ///
+    /// `None` is returned if persistence is not enabled (see
+    /// [`with_persistence()`](HlcGenerator::with_persistence)), or nothing has
+    /// been persisted yet.
+    pub fn high_water_mark(&self) -> Option<i64> {
+        self.high_water_mark
+            .as_ref()
+            .map(HighWaterMark::value)
+            .filter(|&value| value != i64::MIN)
+    }
+
+    /// Counts the drift violation, and quarantines the offending peer, if
+    /// required by the policy.
+    fn drift_violation(&self, peer: u64, incoming_pt: i64, timestamp: i64, skew: i64, max: usize) {
+        self.drift_violations
+            .fetch_add(1, atomic::Ordering::Relaxed);
+        telemetry::drift_violation(peer, incoming_pt, timestamp, skew, max, self.drift_policy);
+        if self.drift_policy == DriftPolicy::Quarantine {
+            self.quarantined.write().insert(peer);
         }
     }
 
+    /// Records `n` issued timestamps, ending with `last`, given the state the
+    /// update started from and the wall-clock time.
+    fn issued(
+        &self,
+        event: &'static str,
+        n: u64,
+        prev: (i64, u64),
+        last: HlcTimestampOf<L, E>,
+        timestamp: i64,
+    ) {
+        let lc_max = HlcTimestampOf::<L, E>::LC_MAX;
+        telemetry::logical_clock(prev, last.parts(), lc_max, timestamp);
+        telemetry::timestamps_issued(event, n, prev.0, last.timestamp(), timestamp);
+    }
+
+    /// Makes sure the timestamp is below the persisted high-water mark, before
+    /// it is handed out.
+    fn reserve(&self, ts: HlcTimestampOf<L, E>) -> HlcResult<HlcTimestampOf<L, E>> {
+        if let Some(hwm) = &self.high_water_mark {
+            hwm.reserve(ts.timestamp())?;
+        }
+        Ok(ts)
+    }
+
     /// Current timestamp.
     ///
     /// Use [`next_timestamp()`](HlcGenerator::next_timestamp) to get the
     /// timestamp for local or send events.
-    pub fn timestamp(&self) -> HlcTimestamp {
+    pub fn timestamp(&self) -> HlcTimestampOf<L, E> {
         self.state.snapshot()
     }
 
     /// Timestamp for the local or send event.
-    pub fn next_timestamp(&self) -> Option<HlcTimestamp> {
-        let timestamp = self.clock.current_timestamp();
+    ///
+    /// If the logical clock overflows, the configured [`OverflowPolicy`] is
+    /// applied.
+    ///
+    /// `None` is returned if no timestamp can be generated, use
+    /// [`try_next_timestamp()`](HlcGenerator::try_next_timestamp) to find out
+    /// why.
+    pub fn next_timestamp(&self) -> Option<HlcTimestampOf<L, E>> {
+        self.try_next_timestamp().ok()
+    }
 
-        self.state
-            .update(move |pt, lc| {
-                // Update the physical time and increment the logical count.
-                if pt >= timestamp {
-                    Ok((pt, lc + 1))
+    /// Timestamp for the local or send event.
+    ///
+    /// Same as [`next_timestamp()`](HlcGenerator::next_timestamp), but the
+    /// reason of failure is reported:
+    /// - [`HlcError::TimestampBelowMin`], if the logical clock overflows while
+    ///   the wall-clock time precedes the epoch (e.g. the clock is reset to
+    ///   1970, so the physical time cannot advance).
+    /// - [`HlcError::LogicalClockOverflow`], if the logical clock overflows
+    ///   otherwise (and [`OverflowPolicy::Wait`] is not used). Both the
+    ///   physical time of the generator and the wall-clock time are reported.
+    /// - [`HlcError::PhysicalTimeExceedsMax`], if the wall-clock time is beyond
+    ///   the range of the layout.
+    /// - [`HlcError::Persistence`], if the high-water mark cannot be persisted.
+    pub fn try_next_timestamp(&self) -> HlcResult<HlcTimestampOf<L, E>> {
+        let borrow_next = self.overflow_policy == OverflowPolicy::BorrowNext;
+        // Whether the request has already been counted as an overflow.
+        let mut waited = false;
+
+        loop {
+            let timestamp = self.clock.current_timestamp();
+            self.regressions.observe(
+                timestamp,
+                || self.clock.current_timestamp(),
+                || self.timestamp().timestamp(),
+            );
+
+            // State the last attempt of the update started from.
+            let prev = Cell::new((0, 0));
+            let res = self
+                .state
+                .update(|pt, lc| {
+                    prev.set((pt, lc));
+                    // Update the physical time and increment the logical count.
+                    if pt >= timestamp {
+                        if borrow_next && lc >= HlcTimestampOf::<L, E>::LC_MAX {
+                            // Logical clock is exhausted, move to the next millisecond.
+                            return Ok((pt + 1, 0));
+                        }
+                        Ok((pt, lc + 1))
+                    } else {
+                        Ok((timestamp, 0))
+                    }
+                })
+                .map(|ts| ts.snapshot());
+
+            match res {
+                Ok(ts) => {
+                    // Logical clock is reset only when moving to the wall-clock time, unless
+                    // the millisecond has been borrowed.
+                    if ts.count() == 0 && ts.timestamp() > timestamp {
+                        self.overflows.fetch_add(1, atomic::Ordering::Relaxed);
+                    }
+                    let ts = self.reserve(ts)?;
+                    self.issued("local", 1, prev.get(), ts, timestamp);
+                    return Ok(ts);
+                }
+                Err(HlcError::LogicalClockExceedsMax(..)) => {
+                    if !waited {
+                        self.overflows.fetch_add(1, atomic::Ordering::Relaxed);
+                    }
+                    // Wall-clock will not catch up any time soon, no point in waiting.
+                    if timestamp < E::UNIX_MILLIS {
+                        return Err(HlcError::TimestampBelowMin(timestamp, E::UNIX_MILLIS));
+                    }
+                    if self.overflow_policy != OverflowPolicy::Wait {
+                        let pt = self.state.snapshot().timestamp();
+                        return Err(HlcError::LogicalClockOverflow(pt, timestamp));
+                    }
+                    waited = true;
+                    std::thread::yield_now();
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Reserves `n` consecutive timestamps for local or send events.
+    ///
+    /// The timestamps are reserved atomically, with a single update of the
+    /// generator state, which is much cheaper than calling
+    /// [`next_timestamp()`](HlcGenerator::next_timestamp) `n` times.
+    ///
+    /// If the logical clock overflows, the configured [`OverflowPolicy`] is
+    /// applied to the whole range:
+    /// - [`OverflowPolicy::Error`]: [`HlcError::LogicalClockExceedsMax`] is
+    ///   returned, i.e. the range must fit into the logical clock of a single
+    ///   millisecond.
+    /// - [`OverflowPolicy::BorrowNext`]: the range spills over into the next
+    ///   millisecond(s), i.e. the physical time is advanced ahead of the
+    ///   wall-clock time, but at most by the maximum drift (or by a second, if
+    ///   the drift check is disabled). [`HlcError::LogicalClockOverflow`] is
+    ///   returned for larger ranges, with the physical time the range would end
+    ///   at.
+    /// - [`OverflowPolicy::Wait`]: the current thread yields until the
+    ///   wall-clock time ticks past the physical time of the generator. Ranges
+    ///   larger than the logical clock fail with
+    ///   [`HlcError::LogicalClockExceedsMax`] right away, as they never fit.
+    ///
+    /// The generator state is left intact, if the range cannot be reserved.
+    /// Overflows are counted by [`overflows()`](HlcGenerator::overflows).
+    ///
+    /// ```
+    /// use hlc_gen::HlcGenerator;
+    ///
+    /// let g = HlcGenerator::new(0);
+    /// let timestamps = g.next_timestamps(1000).unwrap().collect::<Vec<_>>();
+    /// assert_eq!(timestamps.len(), 1000);
+    /// assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+    /// assert!(g.next_timestamp().unwrap() > timestamps[999]);
+    /// ```
+    pub fn next_timestamps(&self, n: u64) -> HlcResult<HlcTimestampRange<L, E>> {
+        if n == 0 {
+            return Ok(HlcTimestampRange::ending_at(self.timestamp(), 0));
+        }
+
+        let span = n - 1;
+        let lc_max = HlcTimestampOf::<L, E>::LC_MAX;
+        let borrow_next = self.overflow_policy == OverflowPolicy::BorrowNext;
+        // How far the range may run ahead of the wall-clock time.
+        let max_ahead = match self.max_drift {
+            0 => MAX_BORROWED_MS,
+            max_drift => i64::try_from(max_drift).unwrap_or(i64::MAX),
+        };
+        // Whether the request has already been counted as an overflow.
+        let mut waited = false;
+
+        loop {
+            let timestamp = self.clock.current_timestamp();
+            self.regressions.observe(
+                timestamp,
+                || self.clock.current_timestamp(),
+                || self.timestamp().timestamp(),
+            );
+
+            // State the last attempt of the update started from.
+            let prev = Cell::new((0, 0));
+            let res = self.state.update(|pt, lc| {
+                prev.set((pt, lc));
+                // Start of the range, same as for a single timestamp.
+                let (pt, lc) = if pt >= timestamp {
+                    (pt, lc + 1)
                 } else {
-                    Ok((timestamp, 0))
+                    (timestamp, 0)
+                };
+
+                // End of the range, within the same millisecond, if possible.
+                let end = lc.saturating_add(span);
+                if end <= lc_max {
+                    return Ok((pt, end));
+                }
+                if !borrow_next {
+                    return Err(HlcError::LogicalClockExceedsMax(end, lc_max));
                 }
-            })
-            .map(|ts| ts.snapshot())
-            .ok()
+
+                // Otherwise, carry over into the borrowed milliseconds.
+                let lc_range = u128::from(lc_max) + 1;
+                let end = u128::from(lc) + u128::from(span);
+                let last_pt = i64::try_from(end / lc_range)
+                    .map_or(i64::MAX, |carry| pt.saturating_add(carry));
+                if last_pt.saturating_sub(timestamp) > max_ahead {
+                    return Err(HlcError::LogicalClockOverflow(last_pt, timestamp));
+                }
+                Ok((last_pt, (end % lc_range) as u64))
+            });
+
+            match res {
+                Ok(last) => {
+                    let last = last.snapshot();
+                    let range = HlcTimestampRange::ending_at(last, n);
+                    // Either the range spans several milliseconds, or starts in a borrowed one.
+                    let spilled = range.start().is_some_and(|first| {
+                        first.timestamp() < last.timestamp()
+                            || (first.count() == 0 && first.timestamp() > timestamp)
+                    });
+                    if spilled && !waited {
+                        self.overflows.fetch_add(1, atomic::Ordering::Relaxed);
+                    }
+                    self.reserve(last)?;
+                    self.issued("local", n, prev.get(), last, timestamp);
+                    return Ok(range);
+                }
+                Err(
+                    err @ (HlcError::LogicalClockExceedsMax(..)
+                    | HlcError::LogicalClockOverflow(..)),
+                ) => {
+                    if !waited {
+                        self.overflows.fetch_add(1, atomic::Ordering::Relaxed);
+                    }
+                    // Range larger than the logical clock never fits, no point in waiting.
+                    if self.overflow_policy != OverflowPolicy::Wait || span > lc_max {
+                        return Err(err);
+                    }
+                    if timestamp < E::UNIX_MILLIS {
+                        return Err(HlcError::TimestampBelowMin(timestamp, E::UNIX_MILLIS));
+                    }
+                    waited = true;
+                    std::thread::yield_now();
+                }
+                Err(err) => return Err(err),
+            }
+        }
     }
 
     /// Adjust the clock based on incoming timestamp.
     ///
     /// Usually this happens when a timestamp is received from another node.
-    /// An error may occur if drift is exceeded (if `max_drift` is set to 0,
-    /// then such a check is ignored).
+    /// If the incoming timestamp is ahead of the wall-clock time by more than
+    /// the maximum drift, or lags behind it by more than the maximum lag, the
+    /// configured [`DriftPolicy`] is applied (if `max_drift` or `max_lag` is
+    /// set to 0, then the respective check is ignored):
+    /// - [`HlcError::DriftTooLarge`] is returned for [`DriftPolicy::Reject`]
+    ///   and [`DriftPolicy::Quarantine`];
+    /// - [`HlcError::PeerQuarantined`] is returned for the timestamps of a
+    ///   quarantined peer.
+    ///
+    /// With [`DriftPolicy::Clamp`], the updated timestamp precedes the incoming
+    /// one, if the latter is clamped. Such updates are counted by
+    /// [`clamped()`](HlcGenerator::clamped), and reported as drift violations
+    /// (with the `clamp` policy) by the telemetry.
+    ///
+    /// With [`DriftPolicy::Quarantine`], peers are identified by the node
+    /// identifier of the incoming timestamp, so [`HlcError::InvalidConfig`] is
+    /// returned, if the layout reserves no bits for it (use
+    /// [`update_from()`](HlcGenerator::update_from) instead). Otherwise, the
+    /// node identifier of the incoming timestamp is ignored, i.e. the updated
+    /// timestamp always carries the identifier of the local node.
     ///
     /// Updated timestamp is returned.
-    pub fn update(&self, incoming_state: &HlcTimestamp) -> HlcResult<HlcTimestamp> {
-        let max_drift = self.max_drift;
+    ///
+    /// ```
+    /// use hlc_gen::{HlcGenerator, HlcTimestamp, config::DriftPolicy, source::ManualClock};
+    ///
+    /// let g = HlcGenerator::builder()
+    ///     .clock(ManualClock::new(1_746_057_600_000))
+    ///     .max_drift(1000)
+    ///     .drift_policy(DriftPolicy::Clamp)
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// // Peer is an hour ahead, but the generator moves at most a second ahead.
+    /// let incoming = HlcTimestamp::from_parts(1_746_061_200_000, 0).unwrap();
+    /// let ts = g.update(&incoming).unwrap();
+    /// assert_eq!(ts.parts(), (1_746_057_601_000, 0));
+    /// assert!(ts < incoming);
+    /// assert_eq!(g.clamped(), 1);
+    /// ```
+    pub fn update(&self, incoming_state: &HlcTimestampOf<L, E>) -> HlcResult<HlcTimestampOf<L, E>> {
+        if self.drift_policy == DriftPolicy::Quarantine && L::NODE_BITS == 0 {
+            return Err(HlcError::InvalidConfig(
+                "quarantine drift policy requires node identifier bits in the layout, or peer \
+                 identifiers given to update_from()"
+                    .into(),
+            ));
+        }
+        let timestamp = self.clock.current_timestamp();
+        self.merge(incoming_state.node(), incoming_state, timestamp)
+    }
+
+    /// Adjust the clock based on incoming timestamp, received from the peer.
+    ///
+    /// Same as [`update()`](HlcGenerator::update), but the clock skew of the
+    /// peer is recorded (even if the timestamp is rejected), see
+    /// [`peer_skew()`](HlcGenerator::peer_skew). The peer is identified by
+    /// `peer_id` (rather than the node identifier embedded into the
+    /// timestamp), also for the purposes of [`DriftPolicy::Quarantine`], which
+    /// thus works with any layout.
+    ///
+    /// ```
+    /// use hlc_gen::{HlcGenerator, HlcTimestamp, source::ManualClock};
+    ///
+    /// let g = HlcGenerator::builder()
+    ///     .clock(ManualClock::new(1_746_057_600_000))
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// // Peer 7 is 300 ms ahead, and then 100 ms ahead.
+    /// for pt in [1_746_057_600_300, 1_746_057_600_100] {
+    ///     g.update_from(7, &HlcTimestamp::from_parts(pt, 0).unwrap())
+    ///         .unwrap();
+    /// }
+    /// let skew = g.peer_skew(7).unwrap();
+    /// assert_eq!((skew.last, skew.min, skew.max), (100, 100, 300));
+    /// assert!(skew.ewma > 100.0 && skew.ewma < 300.0);
+    /// ```
+    pub fn update_from(
+        &self,
+        peer_id: u64,
+        incoming_state: &HlcTimestampOf<L, E>,
+    ) -> HlcResult<HlcTimestampOf<L, E>> {
         let timestamp = self.clock.current_timestamp();
+        self.skews.record(
+            peer_id,
+            incoming_state.timestamp().saturating_sub(timestamp),
+        );
+        self.merge(peer_id, incoming_state, timestamp)
+    }
 
-        self.state
-            .update(move |pt, lc| {
-                let (incoming_pt, incoming_lc) = incoming_state.parts();
+    /// Merges the incoming timestamp of the peer into the generator state,
+    /// given the wall-clock time.
+    fn merge(
+        &self,
+        peer: u64,
+        incoming_state: &HlcTimestampOf<L, E>,
+        timestamp: i64,
+    ) -> HlcResult<HlcTimestampOf<L, E>> {
+        let max_drift = self.max_drift;
+        let drift_policy = self.drift_policy;
 
-                // Physical clock is ahead of both the incoming timestamp and the current state.
-                if timestamp > incoming_pt && timestamp > pt {
-                    // Update the clock state.
-                    return Ok((timestamp, 0));
-                }
+        if drift_policy == DriftPolicy::Quarantine && self.quarantined.read().contains(&peer) {
+            return Err(HlcError::PeerQuarantined(peer));
+        }
+
+        let (incoming_pt, incoming_lc) = incoming_state.parts();
+        // Positive if the incoming timestamp is ahead of the wall-clock time, negative
+        // if it lags behind.
+        let skew = incoming_pt.saturating_sub(timestamp);
+
+        // Stale timestamps never advance physical time, so there is nothing to clamp.
+        let max_lag = self.max_lag;
+        if max_lag > 0 && skew < 0 && skew.unsigned_abs() > max_lag as u64 {
+            self.drift_violation(peer, incoming_pt, timestamp, skew, max_lag);
+            if matches!(drift_policy, DriftPolicy::Reject | DriftPolicy::Quarantine) {
+                return Err(HlcError::DriftTooLarge(
+                    incoming_pt,
+                    timestamp,
+                    skew,
+                    max_lag,
+                ));
+            }
+        }
+
+        // Whether the drift is exceeded on the last attempt of the update, and the
+        // state it started from.
+        let violation = Cell::new(false);
+        let prev = Cell::new((0, 0));
+        let res = self.state.update(|pt, lc| {
+            violation.set(false);
+            prev.set((pt, lc));
+
+            // Physical clock is ahead of both the incoming timestamp and the current state.
+            if timestamp > incoming_pt && timestamp > pt {
+                // Update the clock state.
+                return Ok((timestamp, 0));
+            }
 
-                match incoming_pt.cmp(&pt) {
-                    // Incoming timestamp is ahead of the current state.
-                    Ordering::Greater => {
-                        // Check for drift.
-                        if max_drift > 0 {
-                            let drift = usize::try_from(incoming_pt - timestamp)
-                                .map_err(|_| HlcError::OutOfRangeTimestamp)?;
-                            if drift > max_drift {
-                                return Err(HlcError::DriftTooLarge(drift, max_drift));
+            match incoming_pt.cmp(&pt) {
+                // Incoming timestamp is ahead of the current state.
+                Ordering::Greater => {
+                    // Check for drift.
+                    if max_drift > 0 && usize::try_from(skew).is_ok_and(|drift| drift > max_drift) {
+                        violation.set(true);
+                        match drift_policy {
+                            DriftPolicy::Reject | DriftPolicy::Quarantine => {
+                                return Err(HlcError::DriftTooLarge(
+                                    incoming_pt,
+                                    timestamp,
+                                    skew,
+                                    max_drift,
+                                ));
                             }
+                            DriftPolicy::Clamp => {
+                                // Advance no further than the maximum drift allows.
+                                let max_pt = timestamp.saturating_add(max_drift as i64);
+                                return Ok(if max_pt > pt {
+                                    (max_pt, 0)
+                                } else {
+                                    (pt, lc + 1)
+                                });
+                            }
+                            DriftPolicy::Report => {}
                         }
-                        // Remote timestamp is ahead of the current state. Update local state.
-                        Ok((incoming_pt, incoming_lc + 1))
-                    }
-                    // Incoming timestamp is behind the current state.
-                    Ordering::Less => {
-                        // Our timestamp is ahead of the incoming timestamp, so it remains
-                        // unchanged. We only need to update the logical
-                        // count.
-                        Ok((pt, lc + 1))
-                    }
-                    // Timestamps are equal, so we need to use the maximum logical count for update.
-                    Ordering::Equal => {
-                        // Timestamps are equal, so we need to use the maximum logical count for
-                        // update.
-                        Ok((pt, lc.max(incoming_lc) + 1))
                     }
+                    // Remote timestamp is ahead of the current state. Update local state.
+                    Ok((incoming_pt, incoming_lc + 1))
                 }
-            })
-            .map(|ts| ts.snapshot())
+                // Incoming timestamp is behind the current state.
+                Ordering::Less => {
+                    // Our timestamp is ahead of the incoming timestamp, so it remains
+                    // unchanged. We only need to update the logical
+                    // count.
+                    Ok((pt, lc + 1))
+                }
+                // Timestamps are equal, so we need to use the maximum logical count for update.
+                Ordering::Equal => {
+                    // Timestamps are equal, so we need to use the maximum logical count for
+                    // update.
+                    Ok((pt, lc.max(incoming_lc) + 1))
+                }
+            }
+        });
+
+        if violation.get() {
+            self.drift_violation(peer, incoming_pt, timestamp, skew, max_drift);
+            if drift_policy == DriftPolicy::Clamp && res.is_ok() {
+                self.clamped.fetch_add(1, atomic::Ordering::Relaxed);
+            }
+        }
+        let ts = self.reserve(res?.snapshot())?;
+        self.issued("receive", 1, prev.get(), ts, timestamp);
+        Ok(ts)
     }
 }
@@ -0,0 +1,203 @@
+use {
+    crate::error::{HlcError, HlcResult},
+    parking_lot::Mutex,
+    std::{
+        fs,
+        io::{ErrorKind, Write},
+        path::PathBuf,
+        sync::atomic::{AtomicI64, Ordering},
+    },
+};
+
+/// Storage of the generator's high-water mark.
+///
+/// The high-water mark is a physical time (in milliseconds since the Unix
+/// epoch), which is guaranteed to be ahead of every timestamp issued by the
+/// generator. When the generator is created with persistence, its state is
+/// seeded with the persisted high-water mark, so it never goes backwards
+/// across restarts, even if the wall-clock does (e.g. because of an NTP step
+/// or a VM snapshot restore).
+pub trait Persistence: Send + Sync {
+    /// Loads the persisted high-water mark, if any.
+    fn load(&self) -> HlcResult<Option<i64>>;
+
+    /// Persists the high-water mark.
+    ///
+    /// Once the call returns, the value must survive restarts.
+    fn store(&self, high_water_mark: i64) -> HlcResult<()>;
+}
+
+/// File-backed persistence.
+///
+/// The high-water mark is stored as a decimal number. Updates are atomic: the
+/// value is written into a temporary file, which is then synced and renamed
+/// (and, on Unix, the directory is synced as well).
+#[derive(Debug)]
+pub struct FilePersistence {
+    /// Path of the file with the high-water mark.
+    path: PathBuf,
+}
+
+impl FilePersistence {
+    /// Creates new file-backed persistence.
+    ///
+    /// The file is created on the first update, if it does not exist.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Path of the temporary file used for updates.
+    fn tmp_path(&self) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(".tmp");
+        path.into()
+    }
+}
+
+impl Persistence for FilePersistence {
+    fn load(&self) -> HlcResult<Option<i64>> {
+        match fs::read_to_string(&self.path) {
+            Ok(s) => s
+                .trim()
+                .parse()
+                .map(Some)
+                .map_err(|e| HlcError::Persistence(format!("{}: {e}", self.path.display()))),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(HlcError::Persistence(format!(
+                "{}: {e}",
+                self.path.display()
+            ))),
+        }
+    }
+
+    fn store(&self, high_water_mark: i64) -> HlcResult<()> {
+        let tmp_path = self.tmp_path();
+        let write = || -> std::io::Result<()> {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(high_water_mark.to_string().as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, &self.path)?;
+            // The rename itself is only durable once the directory is synced.
+            #[cfg(unix)]
+            {
+                let dir = match self.path.parent() {
+                    Some(dir) if !dir.as_os_str().is_empty() => dir,
+                    _ => std::path::Path::new("."),
+                };
+                fs::File::open(dir)?.sync_all()?;
+            }
+            Ok(())
+        };
+        write().map_err(|e| HlcError::Persistence(format!("{}: {e}", self.path.display())))
+    }
+}
+
+/// High-water mark of the generator, backed by persistence.
+pub(crate) struct HighWaterMark {
+    /// Persistence backend.
+    backend: Box<dyn Persistence>,
+
+    /// How far ahead (in milliseconds) of the issued timestamps the high-water
+    /// mark is moved, whenever it is reached.
+    window: i64,
+
+    /// Last persisted high-water mark.
+    value: AtomicI64,
+
+    /// Serializes updates of the persisted value.
+    lock: Mutex<()>,
+}
+
+impl HighWaterMark {
+    /// Loads the high-water mark from the persistence backend.
+    ///
+    /// The window must be non-zero, otherwise the high-water mark would not
+    /// be ahead of the issued timestamps.
+    pub fn load(backend: Box<dyn Persistence>, window: u64) -> HlcResult<Self> {
+        if window == 0 {
+            return Err(HlcError::InvalidConfig(
+                "persistence window must be non-zero".to_string(),
+            ));
+        }
+        let value = backend.load()?.unwrap_or(i64::MIN);
+        Ok(Self {
+            backend,
+            window: i64::try_from(window).unwrap_or(i64::MAX),
+            value: AtomicI64::new(value),
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Last persisted high-water mark.
+    pub fn value(&self) -> i64 {
+        self.value.load(Ordering::Acquire)
+    }
+
+    /// Makes sure the high-water mark is ahead of the given physical time.
+    ///
+    /// Must be called before a timestamp with physical time `pt` is handed
+    /// out. Only hits the persistence backend when the current high-water
+    /// mark is reached.
+    pub fn reserve(&self, pt: i64) -> HlcResult<()> {
+        if pt < self.value() {
+            return Ok(());
+        }
+
+        let _guard = self.lock.lock();
+        // Another thread might have moved the mark while we were waiting.
+        if pt < self.value() {
+            return Ok(());
+        }
+        let value = pt.saturating_add(self.window);
+        self.backend.store(value)?;
+        self.value.store(value, Ordering::Release);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_persistence() {
+        let dir = tempfile::tempdir().unwrap();
+        let persistence = FilePersistence::new(dir.path().join("hlc"));
+        assert_eq!(persistence.load(), Ok(None));
+
+        persistence.store(12345).unwrap();
+        assert_eq!(persistence.load(), Ok(Some(12345)));
+        persistence.store(67890).unwrap();
+        assert_eq!(persistence.load(), Ok(Some(67890)));
+        assert!(!persistence.tmp_path().exists());
+
+        fs::write(dir.path().join("hlc"), "garbage").unwrap();
+        assert!(matches!(persistence.load(), Err(HlcError::Persistence(_))));
+    }
+
+    #[test]
+    fn high_water_mark() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hlc");
+
+        let hwm = HighWaterMark::load(Box::new(FilePersistence::new(&path)), 1000).unwrap();
+        assert_eq!(hwm.value(), i64::MIN);
+
+        // Reaching the mark moves it a window ahead.
+        hwm.reserve(100).unwrap();
+        assert_eq!(hwm.value(), 1100);
+        hwm.reserve(1099).unwrap();
+        assert_eq!(hwm.value(), 1100);
+        hwm.reserve(1100).unwrap();
+        assert_eq!(hwm.value(), 2100);
+
+        // The mark survives restarts.
+        let hwm = HighWaterMark::load(Box::new(FilePersistence::new(&path)), 1000).unwrap();
+        assert_eq!(hwm.value(), 2100);
+
+        assert!(matches!(
+            HighWaterMark::load(Box::new(FilePersistence::new(&path)), 0),
+            Err(HlcError::InvalidConfig(_))
+        ));
+    }
+}
@@ -0,0 +1,47 @@
+use {
+    crate::{HlcTimestampOf, epoch::Epoch, layout::Layout},
+    serde::{Deserialize, Deserializer, Serialize, Serializer, de::Error, ser::SerializeStruct},
+};
+
+/// Structured form of a timestamp, used by human-readable formats.
+#[derive(Deserialize)]
+#[serde(rename = "HlcTimestamp", deny_unknown_fields)]
+struct Parts {
+    timestamp: i64,
+    count: u64,
+    #[serde(default)]
+    node: u64,
+}
+
+impl<L: Layout, E: Epoch> Serialize for HlcTimestampOf<L, E> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_u64(self.as_u64());
+        }
+
+        let has_node = L::NODE_BITS > 0;
+        let mut state = serializer.serialize_struct("HlcTimestamp", 2 + has_node as usize)?;
+        state.serialize_field("timestamp", &self.timestamp())?;
+        state.serialize_field("count", &self.count())?;
+        if has_node {
+            state.serialize_field("node", &self.node())?;
+        }
+        state.end()
+    }
+}
+
+impl<'de, L: Layout, E: Epoch> Deserialize<'de> for HlcTimestampOf<L, E> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if !deserializer.is_human_readable() {
+            let value = u64::deserialize(deserializer)?;
+            return Self::try_from(value).map_err(D::Error::custom);
+        }
+
+        let Parts {
+            timestamp,
+            count,
+            node,
+        } = Parts::deserialize(deserializer)?;
+        Self::from_parts_with_node(timestamp, count, node).map_err(D::Error::custom)
+    }
+}
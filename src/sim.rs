@@ -0,0 +1,415 @@
+use {
+    crate::{
+        HlcGenerator,
+        HlcTimestamp,
+        epoch::EPOCH,
+        source::{ClockSource, SimulatedClock, Timeline},
+    },
+    rand::{Rng, SeedableRng},
+    rand_chacha::ChaCha8Rng,
+    std::{
+        cmp::Reverse,
+        collections::{BTreeMap, BinaryHeap, VecDeque},
+    },
+};
+
+/// True time the simulation starts at (a year past the default epoch, so that
+/// skewed clocks never precede it).
+const START: i64 = EPOCH + 365 * 24 * 60 * 60 * 1000;
+
+/// Configuration of the [`Simulation`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimConfig {
+    /// Number of nodes.
+    pub nodes: usize,
+
+    /// Seed of the random number generator, driving all the decisions of the
+    /// simulation.
+    ///
+    /// The generator (ChaCha8) is portable, so the same seed reproduces the
+    /// same simulation on any platform and with any version of the crate.
+    pub seed: u64,
+
+    /// Duration of the simulation (in milliseconds of the true time).
+    pub duration: u64,
+
+    /// Maximum clock skew (in milliseconds) of a node, in either direction.
+    ///
+    /// Skew of each node is picked randomly.
+    pub max_skew: u64,
+
+    /// Probability (within `0.0..=1.0`) of a node sending a message to a
+    /// random peer, every millisecond.
+    pub send_probability: f64,
+
+    /// Probability (within `0.0..=1.0`) of a node recording a local event,
+    /// every millisecond.
+    pub local_probability: f64,
+
+    /// Minimum delay (in milliseconds) of a message.
+    pub min_delay: u64,
+
+    /// Maximum delay (in milliseconds) of a message.
+    pub max_delay: u64,
+
+    /// Probability (within `0.0..=1.0`) of a message being lost.
+    pub loss_probability: f64,
+
+    /// Whether messages between a pair of nodes may be delivered out of order.
+    pub reorder: bool,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            nodes: 5,
+            seed: 0,
+            duration: 10_000,
+            max_skew: 50,
+            send_probability: 0.2,
+            local_probability: 0.2,
+            min_delay: 1,
+            max_delay: 100,
+            loss_probability: 0.01,
+            reorder: true,
+        }
+    }
+}
+
+/// Violation of the HLC invariants, detected by the [`Simulation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation {
+    /// Event is not ordered after the event that happened before it (either
+    /// the previous event on the same node, or the send event of the received
+    /// message).
+    Causality {
+        /// Node the event happened on.
+        node: usize,
+        /// Timestamp of the preceding event.
+        before: HlcTimestamp,
+        /// Timestamp of the event.
+        after: HlcTimestamp,
+    },
+
+    /// Physical time of the timestamp diverged from the node's clock by more
+    /// than the maximum offset between the clocks.
+    Divergence {
+        /// Node the event happened on.
+        node: usize,
+        /// Physical time of the timestamp.
+        pt: i64,
+        /// Reading of the node's clock.
+        wall: i64,
+    },
+
+    /// Logical clock count exceeded the number of events, which could have
+    /// shared the physical time of the timestamp.
+    CounterBound {
+        /// Node the event happened on.
+        node: usize,
+        /// Logical clock count.
+        count: u64,
+        /// Maximum count allowed.
+        bound: u64,
+    },
+}
+
+/// Outcome of the [`Simulation`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SimReport {
+    /// Number of events (local, send and receive) across all nodes.
+    pub events: u64,
+
+    /// Number of messages sent.
+    pub sent: u64,
+
+    /// Number of messages delivered.
+    pub delivered: u64,
+
+    /// Number of messages lost.
+    pub lost: u64,
+
+    /// Number of messages delivered out of order (w.r.t. other messages
+    /// between the same pair of nodes).
+    pub reordered: u64,
+
+    /// Maximum divergence (in milliseconds) of the physical time of a
+    /// timestamp from the node's clock.
+    pub max_divergence: i64,
+
+    /// Maximum logical clock count.
+    pub max_count: u64,
+
+    /// Detected violations of the HLC invariants.
+    pub violations: Vec<Violation>,
+}
+
+impl SimReport {
+    /// Whether all the HLC invariants hold.
+    pub fn is_ok(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// Message in flight.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Message {
+    /// True time of the delivery.
+    deliver_at: i64,
+    /// Sequence number of the message (across all nodes).
+    seq: u64,
+    /// Sending node.
+    from: usize,
+    /// Receiving node.
+    to: usize,
+    /// Timestamp of the send event.
+    ts: HlcTimestamp,
+}
+
+/// Deterministic multi-node HLC simulation.
+///
+/// Nodes are generators driven by [`SimulatedClock`]s, skewed randomly, on a
+/// shared [`Timeline`]. Every millisecond of the true time, each node may
+/// record a local event, or send a message to a random peer; messages are
+/// delayed, lost and reordered randomly. All the decisions are made by an RNG
+/// seeded from [`SimConfig::seed`], so a run is fully reproducible.
+///
+/// Every event is checked against the invariants of the HLC (see the
+/// [paper](https://cse.buffalo.edu/tech-reports/2014-04.pdf)):
+/// - causality is preserved, i.e. an event is timestamped after all the events
+///   that happened before it;
+/// - physical time of the timestamps stays ahead of the node's clock, by at
+///   most the maximum offset between the clocks (`2 * max_skew`);
+/// - logical clock count is bounded by the number of events, which happened
+///   within that offset.
+///
+/// ```
+/// use hlc_gen::sim::{SimConfig, Simulation};
+///
+/// let report = Simulation::new(SimConfig {
+///     nodes: 3,
+///     seed: 42,
+///     duration: 1000,
+///     ..Default::default()
+/// })
+/// .run();
+/// assert!(report.is_ok(), "{:?}", report.violations);
+/// assert!(report.delivered > 0);
+/// ```
+pub struct Simulation {
+    /// Configuration of the simulation.
+    config: SimConfig,
+
+    /// Random number generator.
+    rng: ChaCha8Rng,
+
+    /// Shared timeline of the nodes.
+    timeline: Timeline,
+
+    /// Nodes of the simulation.
+    nodes: Vec<HlcGenerator<SimulatedClock>>,
+
+    /// Last timestamp of each node.
+    last: Vec<HlcTimestamp>,
+
+    /// Messages in flight, ordered by delivery time.
+    in_flight: BinaryHeap<Reverse<Message>>,
+
+    /// Last delivery time on each channel, to keep channels FIFO.
+    channels: BTreeMap<(usize, usize), i64>,
+
+    /// True time of the recent events, to bound the logical clock count.
+    recent: VecDeque<i64>,
+
+    /// Outcome of the simulation.
+    report: SimReport,
+}
+
+impl Simulation {
+    /// Creates a new simulation.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the probabilities is not within `0.0..=1.0`.
+    pub fn new(config: SimConfig) -> Self {
+        for (name, p) in [
+            ("send", config.send_probability),
+            ("local", config.local_probability),
+            ("loss", config.loss_probability),
+        ] {
+            assert!(
+                (0.0..=1.0).contains(&p),
+                "{name} probability must be within 0.0..=1.0, got {p}"
+            );
+        }
+
+        let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+        let timeline = Timeline::new(START);
+        let max_skew = i64::try_from(config.max_skew).unwrap_or(i64::MAX);
+        let nodes = (0..config.nodes)
+            .map(|_| {
+                let skew = rng.random_range(-max_skew..=max_skew);
+                HlcGenerator::builder()
+                    .clock(timeline.clock().with_skew(skew))
+                    .build()
+                    .expect("default config is always valid")
+            })
+            .collect::<Vec<_>>();
+        let last = nodes.iter().map(HlcGenerator::timestamp).collect();
+        Self {
+            config,
+            rng,
+            timeline,
+            nodes,
+            last,
+            in_flight: BinaryHeap::new(),
+            channels: BTreeMap::new(),
+            recent: VecDeque::new(),
+            report: SimReport::default(),
+        }
+    }
+
+    /// Nodes of the simulation.
+    pub fn nodes(&self) -> &[HlcGenerator<SimulatedClock>] {
+        &self.nodes
+    }
+
+    /// Shared timeline of the nodes.
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
+    }
+
+    /// Runs the simulation to completion.
+    pub fn run(mut self) -> SimReport {
+        for _ in 0..self.config.duration {
+            self.step();
+        }
+        self.report
+    }
+
+    /// Simulates a single millisecond of the true time.
+    pub fn step(&mut self) {
+        let now = self.timeline.advance(1);
+
+        // Deliver the messages due.
+        while let Some(Reverse(msg)) = self.in_flight.peek() {
+            if msg.deliver_at > now {
+                break;
+            }
+            let Reverse(msg) = self.in_flight.pop().expect("message is present");
+            self.deliver(msg);
+        }
+
+        for node in 0..self.nodes.len() {
+            if self.rng.random_bool(self.config.local_probability)
+                && let Some(ts) = self.nodes[node].next_timestamp()
+            {
+                self.record(node, ts, None);
+            }
+            if self.nodes.len() > 1 && self.rng.random_bool(self.config.send_probability) {
+                self.send(node);
+            }
+        }
+    }
+
+    /// Outcome of the simulation so far.
+    pub fn report(&self) -> &SimReport {
+        &self.report
+    }
+
+    /// Sends a message from the node to a random peer.
+    fn send(&mut self, from: usize) {
+        let Some(ts) = self.nodes[from].next_timestamp() else {
+            return;
+        };
+        self.record(from, ts, None);
+        self.report.sent += 1;
+
+        let mut to = self.rng.random_range(0..self.nodes.len() - 1);
+        if to >= from {
+            to += 1;
+        }
+        let delay = self
+            .rng
+            .random_range(self.config.min_delay..=self.config.max_delay.max(self.config.min_delay));
+        if self.rng.random_bool(self.config.loss_probability) {
+            self.report.lost += 1;
+            return;
+        }
+
+        let seq = self.report.sent;
+        let mut deliver_at = self.timeline.now() + delay as i64;
+        let last_delivery = self.channels.entry((from, to)).or_insert(i64::MIN);
+        if deliver_at < *last_delivery {
+            if self.config.reorder {
+                self.report.reordered += 1;
+            } else {
+                // Messages with equal delivery times are delivered in order of sending.
+                deliver_at = *last_delivery;
+            }
+        }
+        *last_delivery = deliver_at.max(*last_delivery);
+
+        self.in_flight.push(Reverse(Message {
+            deliver_at,
+            seq,
+            from,
+            to,
+            ts,
+        }));
+    }
+
+    /// Delivers the message to its recipient.
+    fn deliver(&mut self, msg: Message) {
+        if let Ok(ts) = self.nodes[msg.to].update(&msg.ts) {
+            self.report.delivered += 1;
+            self.record(msg.to, ts, Some(msg.ts));
+        }
+    }
+
+    /// Records the event, checking the HLC invariants.
+    fn record(&mut self, node: usize, ts: HlcTimestamp, received: Option<HlcTimestamp>) {
+        let now = self.timeline.now();
+        self.report.events += 1;
+
+        // Causality: both the previous local event and the send event happened
+        // before this one.
+        for before in [Some(self.last[node]), received].into_iter().flatten() {
+            if before >= ts {
+                self.report.violations.push(Violation::Causality {
+                    node,
+                    before,
+                    after: ts,
+                });
+            }
+        }
+        self.last[node] = ts;
+
+        // Bounded divergence from the node's clock.
+        let pt = ts.timestamp();
+        let wall = self.nodes[node].clock.current_timestamp();
+        let epsilon = 2 * self.config.max_skew as i64;
+        let divergence = pt - wall;
+        self.report.max_divergence = self.report.max_divergence.max(divergence);
+        if divergence < 0 || divergence > epsilon {
+            self.report
+                .violations
+                .push(Violation::Divergence { node, pt, wall });
+        }
+
+        // Logical clock count is bounded by the number of events sharing the
+        // physical time, which all happened within the maximum offset.
+        self.recent.push_back(now);
+        while self.recent.front().is_some_and(|&t| t < now - epsilon) {
+            self.recent.pop_front();
+        }
+        let count = ts.count();
+        let bound = self.recent.len() as u64;
+        self.report.max_count = self.report.max_count.max(count);
+        if count > bound {
+            self.report
+                .violations
+                .push(Violation::CounterBound { node, count, bound });
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use {
+    parking_lot::{Mutex, RwLock},
+    std::collections::HashMap,
+};
+
+/// Weight of the latest sample in the exponentially weighted moving average
+/// (same as for the smoothed round-trip time in TCP).
+const EWMA_WEIGHT: f64 = 0.125;
+
+/// Clock skew statistics of a peer, collected by
+/// [`HlcGenerator::update_from()`](crate::HlcGenerator::update_from).
+///
+/// Skew is the difference (in milliseconds) between the physical time of the
+/// incoming timestamp and the local wall-clock time: positive if the peer is
+/// ahead, negative if it lags behind (which includes the message delay).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeerSkew {
+    /// Skew of the latest timestamp.
+    pub last: i64,
+
+    /// Minimum skew observed.
+    pub min: i64,
+
+    /// Maximum skew observed.
+    pub max: i64,
+
+    /// Exponentially weighted moving average of the skew, which smooths out
+    /// the jitter of the message delays.
+    pub ewma: f64,
+
+    /// Number of timestamps observed.
+    pub samples: u64,
+}
+
+impl PeerSkew {
+    /// Creates statistics from the first sample.
+    fn new(skew: i64) -> Self {
+        Self {
+            last: skew,
+            min: skew,
+            max: skew,
+            ewma: skew as f64,
+            samples: 1,
+        }
+    }
+
+    /// Adds the sample to the statistics.
+    fn record(&mut self, skew: i64) {
+        self.last = skew;
+        self.min = self.min.min(skew);
+        self.max = self.max.max(skew);
+        self.ewma += EWMA_WEIGHT * (skew as f64 - self.ewma);
+        self.samples += 1;
+    }
+}
+
+/// Concurrent map of the per-peer skew statistics.
+///
+/// Statistics of the known peers are updated under the read lock of the map,
+/// so the peers do not contend with each other; the write lock is only taken
+/// to add a new peer.
+#[derive(Debug, Default)]
+pub(crate) struct SkewTracker(RwLock<HashMap<u64, Mutex<PeerSkew>>>);
+
+impl SkewTracker {
+    /// Records the skew of the peer.
+    pub(crate) fn record(&self, peer: u64, skew: i64) {
+        if let Some(stats) = self.0.read().get(&peer) {
+            stats.lock().record(skew);
+            return;
+        }
+        self.0
+            .write()
+            .entry(peer)
+            .and_modify(|stats| stats.get_mut().record(skew))
+            .or_insert_with(|| Mutex::new(PeerSkew::new(skew)));
+    }
+
+    /// Statistics of the peer, if any timestamps have been observed.
+    pub(crate) fn get(&self, peer: u64) -> Option<PeerSkew> {
+        self.0.read().get(&peer).map(|stats| *stats.lock())
+    }
+
+    /// Statistics of all the peers, ordered by the peer identifier.
+    pub(crate) fn all(&self) -> Vec<(u64, PeerSkew)> {
+        let mut all = self
+            .0
+            .read()
+            .iter()
+            .map(|(&peer, stats)| (peer, *stats.lock()))
+            .collect::<Vec<_>>();
+        all.sort_unstable_by_key(|&(peer, _)| peer);
+        all
+    }
+
+    /// Drops the statistics of the peer.
+    pub(crate) fn remove(&self, peer: u64) -> Option<PeerSkew> {
+        self.0.write().remove(&peer).map(Mutex::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let tracker = SkewTracker::default();
+        assert_eq!(tracker.get(1), None);
+
+        tracker.record(1, 100);
+        tracker.record(1, -20);
+        tracker.record(1, 40);
+        tracker.record(2, 0);
+
+        let stats = tracker.get(1).unwrap();
+        assert_eq!(
+            (stats.last, stats.min, stats.max, stats.samples),
+            (40, -20, 100, 3)
+        );
+        // 100 -> 100 - 0.125 * 120 = 85 -> 85 - 0.125 * 45 = 79.375
+        assert_eq!(stats.ewma, 79.375);
+
+        assert_eq!(
+            tracker
+                .all()
+                .iter()
+                .map(|(peer, _)| *peer)
+                .collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert_eq!(tracker.remove(2).map(|stats| stats.samples), Some(1));
+        assert_eq!(tracker.remove(2), None);
+    }
+}
@@ -1,14 +1,51 @@
-use {crate::epoch::EPOCH, chrono::Utc, parking_lot::RwLock};
+use {
+    crate::epoch::EPOCH,
+    chrono::Utc,
+    portable_atomic::AtomicU128,
+    std::{
+        sync::{
+            Arc,
+            atomic::{AtomicBool, AtomicI64, AtomicU64, Ordering},
+        },
+        thread::{self, JoinHandle},
+        time::{Duration, Instant},
+    },
+};
 
 /// Provides current time.
-pub trait ClockSource: Default {
+///
+/// Sources, which need no configuration, should implement [`Default`], so that
+/// generators can be created with
+/// [`with_config()`](crate::HlcGenerator::with_config). Configured sources
+/// are passed in via [`with_clock()`](crate::HlcGenerator::with_clock).
+pub trait ClockSource {
     /// The current timestamp in milliseconds since the Unix epoch.
     fn current_timestamp(&self) -> i64;
+
+    /// The current timestamp in nanoseconds since the Unix epoch.
+    ///
+    /// Used by [`Hlc128Generator`](crate::Hlc128Generator). Defaults to the
+    /// millisecond timestamp, for sources with no finer granularity.
+    fn current_timestamp_nanos(&self) -> i64 {
+        self.current_timestamp().saturating_mul(1_000_000)
+    }
+}
+
+/// Shared clock, e.g. a [`ManualClock`] driving several generators in tests.
+impl<T: ClockSource + ?Sized> ClockSource for Arc<T> {
+    fn current_timestamp(&self) -> i64 {
+        (**self).current_timestamp()
+    }
+
+    fn current_timestamp_nanos(&self) -> i64 {
+        (**self).current_timestamp_nanos()
+    }
 }
 
 /// UTC clock.
 ///
-/// Granularity is in milliseconds.
+/// Granularity is in milliseconds (nanoseconds, when used with
+/// [`Hlc128Generator`](crate::Hlc128Generator)).
 #[derive(Default)]
 pub struct UtcClock;
 
@@ -16,14 +53,19 @@ impl ClockSource for UtcClock {
     fn current_timestamp(&self) -> i64 {
         Utc::now().timestamp_millis()
     }
+
+    fn current_timestamp_nanos(&self) -> i64 {
+        Utc::now().timestamp_nanos_opt().unwrap_or(i64::MAX)
+    }
 }
 
 /// Manual clock.
 ///
-/// Useful for testing purposes.
+/// Useful for testing purposes. The timestamp is stored in an atomic, so the
+/// clock can be driven from any thread (e.g. shared via [`Arc`]).
 pub struct ManualClock {
     /// The current timestamp in milliseconds since the Unix epoch.
-    timestamp: RwLock<i64>,
+    timestamp: AtomicI64,
 }
 
 impl Default for ManualClock {
@@ -34,8 +76,7 @@ impl Default for ManualClock {
 
 impl ClockSource for ManualClock {
     fn current_timestamp(&self) -> i64 {
-        let r = self.timestamp.read();
-        *r
+        self.timestamp.load(Ordering::Acquire)
     }
 }
 
@@ -43,13 +84,621 @@ impl ManualClock {
     /// Creates new clock.
     pub fn new(timestamp: i64) -> Self {
         Self {
-            timestamp: RwLock::new(timestamp),
+            timestamp: AtomicI64::new(timestamp),
         }
     }
 
     /// Sets the current timestamp.
     pub fn set_current_timestamp(&self, timestamp: i64) {
-        let mut w = self.timestamp.write();
-        *w = timestamp;
+        self.timestamp.store(timestamp, Ordering::Release);
+    }
+
+    /// Advances the clock by `millis` milliseconds (negative values move the
+    /// clock backwards).
+    ///
+    /// Returns the new timestamp.
+    pub fn advance(&self, millis: i64) -> i64 {
+        self.timestamp.fetch_add(millis, Ordering::AcqRel) + millis
+    }
+}
+
+/// Simulated timeline, shared by [`SimulatedClock`]s.
+///
+/// Cloning the timeline produces another handle to the same timeline, so
+/// nodes of a multi-node test can all be driven by a single "true" time:
+///
+/// ```
+/// use hlc_gen::{HlcGenerator, source::Timeline};
+///
+/// let timeline = Timeline::new(1_746_057_600_000);
+/// let g1 = HlcGenerator::builder()
+///     .clock(timeline.clock())
+///     .build()
+///     .unwrap();
+/// // The second node's clock is 50ms ahead, and is stepped back by 100ms
+/// // after 1.1 seconds.
+/// let g2 = HlcGenerator::builder()
+///     .clock(
+///         timeline
+///             .clock()
+///             .with_skew(50)
+///             .with_jump(1_746_057_601_100, -100),
+///     )
+///     .build()
+///     .unwrap();
+///
+/// timeline.advance(1000);
+/// assert_eq!(g1.next_timestamp().unwrap().timestamp(), 1_746_057_601_000);
+/// assert_eq!(g2.next_timestamp().unwrap().timestamp(), 1_746_057_601_050);
+///
+/// // Clock of the second node is stepped back, physical time holds.
+/// timeline.advance(100);
+/// assert_eq!(g2.next_timestamp().unwrap().timestamp(), 1_746_057_601_050);
+/// ```
+#[derive(Clone)]
+pub struct Timeline {
+    /// The true time in milliseconds since the Unix epoch.
+    now: Arc<ManualClock>,
+}
+
+impl Default for Timeline {
+    fn default() -> Self {
+        Self::new(EPOCH)
+    }
+}
+
+impl Timeline {
+    /// Creates a new timeline, starting at `timestamp` (in milliseconds since
+    /// the Unix epoch).
+    pub fn new(timestamp: i64) -> Self {
+        Self {
+            now: Arc::new(ManualClock::new(timestamp)),
+        }
+    }
+
+    /// The true time in milliseconds since the Unix epoch.
+    pub fn now(&self) -> i64 {
+        self.now.current_timestamp()
+    }
+
+    /// Sets the true time.
+    pub fn set(&self, timestamp: i64) {
+        self.now.set_current_timestamp(timestamp);
+    }
+
+    /// Advances the true time by `millis` milliseconds.
+    ///
+    /// Returns the new true time.
+    pub fn advance(&self, millis: i64) -> i64 {
+        self.now.advance(millis)
+    }
+
+    /// Creates a new clock, reading the true time of this timeline.
+    pub fn clock(&self) -> SimulatedClock {
+        SimulatedClock {
+            timeline: self.clone(),
+            skew: AtomicI64::new(0),
+            jitter: 0,
+            rng: AtomicU64::new(0),
+            jumps: Vec::new(),
+        }
+    }
+}
+
+/// Clock of a simulated node, reading the true time of a [`Timeline`].
+///
+/// The reading is distorted by:
+/// - constant skew (see [`with_skew()`](SimulatedClock::with_skew));
+/// - scheduled jumps, applied once the true time reaches them (see
+///   [`with_jump()`](SimulatedClock::with_jump));
+/// - random jitter (see [`with_jitter()`](SimulatedClock::with_jitter)),
+///   generated deterministically from the seed.
+pub struct SimulatedClock {
+    /// Timeline providing the true time.
+    timeline: Timeline,
+
+    /// Offset (in milliseconds) from the true time.
+    skew: AtomicI64,
+
+    /// Maximum jitter (in milliseconds) of readings.
+    jitter: u64,
+
+    /// State of the jitter generator.
+    rng: AtomicU64,
+
+    /// Scheduled jumps, as the true time and the offset (in milliseconds)
+    /// applied from that time on.
+    jumps: Vec<(i64, i64)>,
+}
+
+impl SimulatedClock {
+    /// Sets the offset (in milliseconds) from the true time.
+    pub fn with_skew(self, skew: i64) -> Self {
+        self.skew.store(skew, Ordering::Relaxed);
+        self
+    }
+
+    /// Adds random jitter of up to `jitter` milliseconds (in either direction)
+    /// to every reading.
+    ///
+    /// The jitter sequence is determined by the `seed`.
+    pub fn with_jitter(mut self, jitter: u64, seed: u64) -> Self {
+        self.jitter = jitter;
+        self.rng = AtomicU64::new(seed);
+        self
+    }
+
+    /// Schedules a jump of the clock by `offset` milliseconds, once the true
+    /// time reaches `at`.
+    pub fn with_jump(mut self, at: i64, offset: i64) -> Self {
+        self.jumps.push((at, offset));
+        self
+    }
+
+    /// Timeline the clock reads.
+    pub fn timeline(&self) -> &Timeline {
+        &self.timeline
+    }
+
+    /// Offset (in milliseconds) from the true time.
+    pub fn skew(&self) -> i64 {
+        self.skew.load(Ordering::Relaxed)
+    }
+
+    /// Changes the offset (in milliseconds) from the true time.
+    pub fn set_skew(&self, skew: i64) {
+        self.skew.store(skew, Ordering::Relaxed);
+    }
+
+    /// Next jitter value, in `-jitter..=jitter`.
+    fn next_jitter(&self) -> i64 {
+        if self.jitter == 0 {
+            return 0;
+        }
+        // SplitMix64.
+        let mut z = self
+            .rng
+            .fetch_add(0x9e37_79b9_7f4a_7c15, Ordering::Relaxed)
+            .wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        let span = self.jitter.saturating_mul(2).saturating_add(1);
+        (z % span) as i64 - self.jitter as i64
+    }
+}
+
+impl ClockSource for SimulatedClock {
+    fn current_timestamp(&self) -> i64 {
+        let now = self.timeline.now();
+        let jumps = self
+            .jumps
+            .iter()
+            .filter(|(at, _)| *at <= now)
+            .map(|(_, offset)| offset)
+            .sum::<i64>();
+        now + self.skew() + jumps + self.next_jitter()
+    }
+}
+
+/// Clock anchored to the monotonic clock.
+///
+/// The wall-clock time is read on creation, and then advanced with [`Instant`],
+/// so steps of the wall-clock (e.g. NTP corrections) neither stall nor jump the
+/// physical time. Instead, the clock is slewed towards the wall-clock time
+/// (read again at most once per millisecond), at most by `max_slew` parts per
+/// million of the elapsed time, i.e. it never goes backwards, and converges
+/// with the wall-clock time eventually. Reading the clock is lock-free.
+///
+/// The wall-clock time is provided by the clock source `W` (defaults to
+/// [`UtcClock`]).
+///
+/// ```
+/// use {
+///     hlc_gen::{HlcGenerator, source::MonotonicClock},
+///     std::sync::Arc,
+/// };
+///
+/// let clock = Arc::new(MonotonicClock::new());
+/// let g = HlcGenerator::builder()
+///     .clock(clock.clone())
+///     .build()
+///     .unwrap();
+/// assert!(g.next_timestamp().is_some());
+///
+/// // How far the clock is from the wall-clock time (in milliseconds).
+/// assert!(clock.offset().abs() < 1000);
+/// ```
+pub struct MonotonicClock<W: ClockSource = UtcClock> {
+    /// Source of the wall-clock time.
+    wall: W,
+
+    /// Monotonic time the clock is anchored at.
+    anchor: Instant,
+
+    /// Wall-clock time (in nanoseconds since the Unix epoch) at the anchor.
+    anchor_nanos: i64,
+
+    /// Maximum slew rate, in parts per million.
+    max_slew: u32,
+
+    /// Slewing state, as of the last reading of the wall-clock: nanoseconds
+    /// elapsed since the anchor (upper 64 bits), and the correction (in
+    /// nanoseconds) applied to the monotonic time (lower 64 bits).
+    state: AtomicU128,
+}
+
+impl Default for MonotonicClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MonotonicClock {
+    /// Default maximum slew rate, in parts per million (same as the one used by
+    /// NTP).
+    pub const DEFAULT_MAX_SLEW: u32 = 500;
+
+    /// How often (in nanoseconds of the monotonic time) the wall-clock time is
+    /// read, to slew towards it.
+    const SYNC_INTERVAL_NANOS: i64 = 1_000_000;
+
+    /// Creates a new clock, anchored at the current UTC time.
+    pub fn new() -> Self {
+        Self::with_wall_clock(UtcClock, Self::DEFAULT_MAX_SLEW)
+    }
+}
+
+impl<W: ClockSource> MonotonicClock<W> {
+    /// Creates a new clock, anchored at the current time of the `wall` clock,
+    /// and slewed towards it at most by `max_slew` parts per million.
+    ///
+    /// The slew rate is capped at 1 000 000 ppm, at which rate the clock may
+    /// stand still, while the wall-clock catches up.
+    pub fn with_wall_clock(wall: W, max_slew: u32) -> Self {
+        Self {
+            anchor_nanos: wall.current_timestamp_nanos(),
+            anchor: Instant::now(),
+            wall,
+            max_slew: max_slew.min(1_000_000),
+            state: AtomicU128::new(0),
+        }
+    }
+
+    /// Difference (in milliseconds) between this clock and the wall-clock
+    /// time.
+    ///
+    /// Positive, if this clock is ahead of the wall-clock.
+    pub fn offset(&self) -> i64 {
+        self.offset_nanos().div_euclid(1_000_000)
+    }
+
+    /// Difference (in nanoseconds) between this clock and the wall-clock
+    /// time.
+    ///
+    /// Positive, if this clock is ahead of the wall-clock.
+    pub fn offset_nanos(&self) -> i64 {
+        self.read()
+            .saturating_sub(self.wall.current_timestamp_nanos())
+    }
+
+    /// Reads the slewed monotonic time (in nanoseconds since the Unix epoch).
+    fn read(&self) -> i64 {
+        let elapsed = i64::try_from(self.anchor.elapsed().as_nanos()).unwrap_or(i64::MAX);
+
+        loop {
+            let current = self.state.load(Ordering::Acquire);
+            let (synced, correction) = ((current >> 64) as i64, current as i64);
+            // Another thread might have done a later reading in the meantime.
+            let elapsed = elapsed.max(synced);
+            let base = self.anchor_nanos.saturating_add(elapsed);
+            if elapsed - synced < MonotonicClock::SYNC_INTERVAL_NANOS {
+                return base.saturating_add(correction);
+            }
+
+            // Move the correction towards the wall-clock, within the slew rate. Readings
+            // since the last synchronization may have been ahead of it by up to the
+            // interval, so the clock is held back by no more than the rest.
+            let since = elapsed - synced;
+            let max_step = (since as i128 * self.max_slew as i128 / 1_000_000) as i64;
+            let max_back = max_step.min(since - MonotonicClock::SYNC_INTERVAL_NANOS);
+            let target = self.wall.current_timestamp_nanos().saturating_sub(base);
+            let correction =
+                correction + target.saturating_sub(correction).clamp(-max_back, max_step);
+
+            let new = ((elapsed as u128) << 64) | correction as u64 as u128;
+            if self
+                .state
+                .compare_exchange(current, new, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+            {
+                return base.saturating_add(correction);
+            }
+        }
+    }
+}
+
+impl<W: ClockSource> ClockSource for MonotonicClock<W> {
+    fn current_timestamp(&self) -> i64 {
+        self.current_timestamp_nanos().div_euclid(1_000_000)
+    }
+
+    fn current_timestamp_nanos(&self) -> i64 {
+        self.read()
+    }
+}
+
+/// Clock caching the current timestamp.
+///
+/// The timestamp is refreshed by a background thread, and read with a single
+/// atomic load, avoiding system calls and date-time conversions on the hot
+/// path. The price is the granularity: the timestamp lags behind the source by
+/// up to the refresh interval (plus scheduling delays).
+///
+/// The background thread is stopped when the clock is dropped.
+///
+/// ```
+/// use hlc_gen::{HlcGenerator, source::CachedClock};
+///
+/// let g = HlcGenerator::builder()
+///     .clock(CachedClock::new())
+///     .build()
+///     .unwrap();
+/// assert!(g.next_timestamp().is_some());
+/// ```
+pub struct CachedClock {
+    /// State shared with the background thread.
+    shared: Arc<CachedState>,
+
+    /// Background thread refreshing the timestamp.
+    ticker: Option<JoinHandle<()>>,
+}
+
+/// State of the [`CachedClock`], shared with the background thread.
+struct CachedState {
+    /// The cached timestamp in milliseconds since the Unix epoch.
+    timestamp: AtomicI64,
+
+    /// Set, when the background thread should exit.
+    stopped: AtomicBool,
+}
+
+impl Default for CachedClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CachedClock {
+    /// Default refresh interval.
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(1);
+
+    /// Creates a new clock, caching the UTC time, refreshed every millisecond.
+    pub fn new() -> Self {
+        Self::with_source(UtcClock, Self::DEFAULT_INTERVAL)
+    }
+
+    /// Creates a new clock, caching the time of the `source` clock, refreshed
+    /// every `interval`.
+    pub fn with_source<S: ClockSource + Send + 'static>(source: S, interval: Duration) -> Self {
+        let shared = Arc::new(CachedState {
+            timestamp: AtomicI64::new(source.current_timestamp()),
+            stopped: AtomicBool::new(false),
+        });
+
+        let state = shared.clone();
+        let ticker = thread::Builder::new()
+            .name("hlc-cached-clock".to_string())
+            .spawn(move || {
+                let mut deadline = Instant::now() + interval;
+                while !state.stopped.load(Ordering::Acquire) {
+                    let now = Instant::now();
+                    if now < deadline {
+                        // Parking may return spuriously, hence the deadline check.
+                        thread::park_timeout(deadline - now);
+                        continue;
+                    }
+                    state
+                        .timestamp
+                        .store(source.current_timestamp(), Ordering::Release);
+                    deadline = now + interval;
+                }
+            })
+            .expect("failed to spawn clock ticker thread");
+
+        Self {
+            shared,
+            ticker: Some(ticker),
+        }
+    }
+}
+
+impl Drop for CachedClock {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::Release);
+        if let Some(ticker) = self.ticker.take() {
+            ticker.thread().unpark();
+            let _ = ticker.join();
+        }
+    }
+}
+
+impl ClockSource for CachedClock {
+    fn current_timestamp(&self) -> i64 {
+        self.shared.timestamp.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// UTC clock, which can be stepped.
+    #[derive(Default)]
+    struct SteppedClock {
+        /// Total step in milliseconds.
+        step: AtomicI64,
+    }
+
+    impl SteppedClock {
+        fn step(&self, millis: i64) {
+            self.step.fetch_add(millis, Ordering::Relaxed);
+        }
+    }
+
+    impl ClockSource for SteppedClock {
+        fn current_timestamp(&self) -> i64 {
+            UtcClock.current_timestamp() + self.step.load(Ordering::Relaxed)
+        }
+    }
+
+    #[test]
+    fn monotonic_clock_ignores_steps() {
+        let wall = Arc::new(SteppedClock::default());
+        // With zero slew rate, the clock follows the monotonic time only.
+        let clock = MonotonicClock::with_wall_clock(wall.clone(), 0);
+
+        let t1 = clock.current_timestamp();
+        wall.step(-60_000);
+        let t2 = clock.current_timestamp();
+        assert!(t2 >= t1);
+        assert!(clock.offset() >= 59_000);
+
+        wall.step(120_000);
+        let t3 = clock.current_timestamp();
+        assert!(t3 >= t2 && t3 < t1 + 1000);
+        assert!(clock.offset() <= -59_000);
+    }
+
+    #[test]
+    fn monotonic_clock_slews() {
+        let wall = Arc::new(SteppedClock::default());
+        // Half of the elapsed time may be used to slew.
+        let started = Instant::now();
+        let clock = MonotonicClock::with_wall_clock(wall.clone(), 500_000);
+
+        // Wall-clock steps back: the clock slows down, but never goes backwards.
+        wall.step(-1000);
+        let start = clock.current_timestamp_nanos();
+        let mut prev = start;
+        for _ in 0..10 {
+            std::thread::sleep(Duration::from_millis(5));
+            let now = clock.current_timestamp_nanos();
+            assert!(now >= prev);
+            prev = now;
+        }
+        // At most half of the elapsed time (however long the sleeps took) is slewed
+        // away, give or take a millisecond of the wall-clock granularity.
+        let offset = clock.offset_nanos();
+        let max_slewed = started.elapsed().as_nanos() as i64 / 2 + 1_000_000;
+        assert!(offset >= 1_000_000_000 - max_slewed && offset < 1_000_000_000);
+
+        // Small step is eliminated completely.
+        wall.step(offset / 1_000_000 - 5);
+        std::thread::sleep(Duration::from_millis(50));
+        clock.current_timestamp_nanos();
+        assert!(clock.offset_nanos().abs() < 1_000_000);
+    }
+
+    #[test]
+    fn monotonic_clock_concurrent() {
+        let wall = Arc::new(SteppedClock::default());
+        // Clock may stand still, while the wall-clock catches up.
+        let clock = Arc::new(MonotonicClock::with_wall_clock(wall.clone(), 1_000_000));
+        wall.step(-1000);
+
+        // Readings never precede the ones completed before, on any thread.
+        let latest = Arc::new(AtomicI64::new(i64::MIN));
+        let handles = (0..4)
+            .map(|_| {
+                let (clock, latest) = (clock.clone(), latest.clone());
+                thread::spawn(move || {
+                    for _ in 0..100_000 {
+                        let before = latest.load(Ordering::Acquire);
+                        let now = clock.current_timestamp_nanos();
+                        assert!(now >= before);
+                        latest.fetch_max(now, Ordering::AcqRel);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        thread::sleep(Duration::from_millis(10));
+        clock.current_timestamp_nanos();
+        assert!(clock.offset() < 1000);
+    }
+
+    #[test]
+    fn cached_clock() {
+        let clock = CachedClock::new();
+        let t1 = clock.current_timestamp();
+        assert!((t1 - UtcClock.current_timestamp()).abs() < 1000);
+
+        // Refreshed in the background.
+        thread::sleep(Duration::from_millis(20));
+        let t2 = clock.current_timestamp();
+        assert!(t2 > t1);
+
+        // Refresh interval is respected.
+        let source = Arc::new(ManualClock::new(EPOCH));
+        let clock = CachedClock::with_source(source.clone(), Duration::from_secs(3600));
+        source.set_current_timestamp(EPOCH + 1000);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(clock.current_timestamp(), EPOCH);
+    }
+
+    #[test]
+    fn manual_clock() {
+        let clock = Arc::new(ManualClock::new(EPOCH));
+        assert_eq!(clock.advance(10), EPOCH + 10);
+        assert_eq!(clock.advance(-20), EPOCH - 10);
+
+        // Can be driven from many threads.
+        let handles = (0..4)
+            .map(|_| {
+                let clock = clock.clone();
+                thread::spawn(move || {
+                    for _ in 0..1000 {
+                        clock.advance(1);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(clock.current_timestamp(), EPOCH + 3990);
+    }
+
+    #[test]
+    fn simulated_clock() {
+        let timeline = Timeline::new(EPOCH);
+        let clock = timeline
+            .clock()
+            .with_skew(-5)
+            .with_jump(EPOCH + 100, 1000)
+            .with_jump(EPOCH + 200, -300);
+        assert_eq!(clock.current_timestamp(), EPOCH - 5);
+        timeline.advance(100);
+        assert_eq!(clock.current_timestamp(), EPOCH + 1095);
+        timeline.set(EPOCH + 250);
+        assert_eq!(clock.current_timestamp(), EPOCH + 945);
+        clock.set_skew(0);
+        assert_eq!(clock.current_timestamp(), EPOCH + 950);
+
+        // Jitter is bounded and deterministic.
+        let readings = |seed| {
+            let clock = timeline.clock().with_jitter(3, seed);
+            (0..100)
+                .map(|_| clock.current_timestamp() - timeline.now())
+                .collect::<Vec<_>>()
+        };
+        let jitter = readings(42);
+        assert!(jitter.iter().all(|j| (-3..=3).contains(j)));
+        assert!(jitter.iter().any(|&j| j != jitter[0]));
+        assert_eq!(jitter, readings(42));
+        assert_ne!(jitter, readings(43));
     }
 }
@@ -0,0 +1,57 @@
+use {
+    crate::{HlcTimestampOf, epoch::Epoch, layout::Layout},
+    proptest::{
+        arbitrary::Arbitrary,
+        strategy::{BoxedStrategy, Strategy},
+    },
+    std::ops::RangeInclusive,
+};
+
+/// Any valid timestamp of the layout and epoch, i.e. physical time, logical
+/// clock count and node identifier all span their full ranges.
+impl<L: Layout, E: Epoch> Arbitrary for HlcTimestampOf<L, E> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        (0..=Self::PT_MAX, 0..=Self::LC_MAX, 0..=Self::NODE_MAX)
+            .prop_map(|(pt, lc, node)| Self::from_raw_parts(pt, lc, node))
+            .boxed()
+    }
+}
+
+/// Timestamps with physical time (in milliseconds since the Unix epoch) within
+/// the given range, and logical clock count within `counts`.
+///
+/// The node identifier spans its full range. The ranges are clamped to the
+/// ones supported by the layout and epoch.
+///
+/// ```
+/// use {
+///     hlc_gen::{HlcTimestamp, strategy},
+///     proptest::{prop_assert, test_runner::TestRunner},
+/// };
+///
+/// let range = 1_746_057_600_000..=1_746_057_700_000;
+/// TestRunner::default()
+///     .run(
+///         &strategy::timestamps(range.clone(), 0..=10),
+///         |ts: HlcTimestamp| {
+///             prop_assert!(range.contains(&ts.timestamp()) && ts.count() <= 10);
+///             Ok(())
+///         },
+///     )
+///     .unwrap();
+/// ```
+pub fn timestamps<L: Layout, E: Epoch>(
+    timestamps: RangeInclusive<i64>,
+    counts: RangeInclusive<u64>,
+) -> impl Strategy<Value = HlcTimestampOf<L, E>> {
+    let max_timestamp = E::UNIX_MILLIS.saturating_add_unsigned(HlcTimestampOf::<L, E>::PT_MAX);
+    let timestamps =
+        (*timestamps.start()).max(E::UNIX_MILLIS)..=(*timestamps.end()).min(max_timestamp);
+    let counts = *counts.start()..=(*counts.end()).min(HlcTimestampOf::<L, E>::LC_MAX);
+    (timestamps, counts, 0..=HlcTimestampOf::<L, E>::NODE_MAX).prop_map(|(pt, lc, node)| {
+        HlcTimestampOf::from_parts_with_node(pt, lc, node).expect("parts are within the range")
+    })
+}
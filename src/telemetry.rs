@@ -0,0 +1,231 @@
+// Instrumentation of the generator. Without the `metrics` and `tracing`
+// features, all the functions are empty and inlined, so the instrumentation
+// costs nothing.
+#![cfg_attr(
+    not(all(feature = "metrics", feature = "tracing")),
+    allow(unused_variables)
+)]
+
+#[cfg(any(feature = "metrics", feature = "tracing"))]
+use std::sync::atomic::{AtomicI64, Ordering};
+
+#[cfg(feature = "metrics")]
+use metrics::{counter, gauge, histogram};
+
+use crate::config::DriftPolicy;
+
+/// Counter of the issued timestamps, labeled by the `event` (`local` for local
+/// or send events, `receive` for receive events).
+pub const TIMESTAMPS_ISSUED: &str = "hlc_timestamps_issued_total";
+
+/// Counter of the failed compare-and-swap attempts of the generator state
+/// update (i.e. contention between threads).
+pub const CAS_RETRIES: &str = "hlc_cas_retries_total";
+
+/// Histogram of the logical clock count reached within a millisecond,
+/// recorded once the generator moves to the next millisecond.
+pub const LOGICAL_CLOCK_HIGH_WATER: &str = "hlc_logical_clock_high_water";
+
+/// Counter of the incoming timestamps exceeding the maximum drift or lag,
+/// labeled by the `direction` (`ahead` or `behind`) and the drift `policy`.
+pub const DRIFT_VIOLATIONS: &str = "hlc_drift_violations_total";
+
+/// Counter of the wall-clock regressions (i.e. the clock going backwards)
+/// observed by the generator.
+pub const CLOCK_REGRESSIONS: &str = "hlc_clock_regressions_total";
+
+/// Histogram of the wall-clock regressions (in milliseconds).
+pub const CLOCK_REGRESSION_MS: &str = "hlc_clock_regression_ms";
+
+/// Gauge of how far (in milliseconds) physical time of the last issued
+/// timestamp is ahead of the wall-clock time.
+pub const PHYSICAL_TIME_AHEAD_MS: &str = "hlc_physical_time_ahead_ms";
+
+/// How far (in milliseconds) physical time may run ahead of the wall-clock
+/// time, before an event is emitted.
+pub const AHEAD_THRESHOLD_MS: i64 = 1000;
+
+/// Share (in percent) of the logical clock range, which may be used within a
+/// millisecond, before an event is emitted.
+pub const SATURATION_THRESHOLD_PERCENT: u64 = 90;
+
+/// Records the issued timestamps, along with how far their physical time is
+/// ahead of the wall-clock time.
+///
+/// Emits an event, if physical time moves from `prev_pt` further ahead of the
+/// wall-clock time than [`AHEAD_THRESHOLD_MS`].
+#[inline]
+pub(crate) fn timestamps_issued(event: &'static str, n: u64, prev_pt: i64, pt: i64, wall: i64) {
+    #[cfg(any(feature = "metrics", feature = "tracing"))]
+    let ahead = pt.saturating_sub(wall);
+
+    #[cfg(feature = "metrics")]
+    {
+        counter!(TIMESTAMPS_ISSUED, "event" => event).increment(n);
+        gauge!(PHYSICAL_TIME_AHEAD_MS).set(ahead as f64);
+    }
+
+    #[cfg(feature = "tracing")]
+    if ahead > AHEAD_THRESHOLD_MS && prev_pt.saturating_sub(wall) <= AHEAD_THRESHOLD_MS {
+        tracing::info!(
+            event,
+            prev_pt,
+            pt,
+            wall,
+            ahead,
+            "HLC physical time runs ahead of the wall-clock time"
+        );
+    }
+}
+
+/// Records the failed compare-and-swap attempts.
+#[inline]
+pub(crate) fn cas_retries(retries: u64) {
+    #[cfg(feature = "metrics")]
+    if retries > 0 {
+        counter!(CAS_RETRIES).increment(retries);
+    }
+}
+
+/// Records the logical clock count the previous millisecond ended with, if
+/// the generator moved from `prev_pt` to the next millisecond(s).
+///
+/// Emits an event, if the logical clock count crosses the
+/// [`SATURATION_THRESHOLD_PERCENT`] of `lc_max`.
+#[inline]
+pub(crate) fn logical_clock(
+    (prev_pt, prev_lc): (i64, u64),
+    (pt, lc): (i64, u64),
+    lc_max: u64,
+    wall: i64,
+) {
+    #[cfg(feature = "metrics")]
+    if pt > prev_pt {
+        histogram!(LOGICAL_CLOCK_HIGH_WATER).record(prev_lc as f64);
+    }
+
+    #[cfg(feature = "tracing")]
+    {
+        let threshold =
+            (u128::from(lc_max) * u128::from(SATURATION_THRESHOLD_PERCENT) / 100) as u64;
+        if lc >= threshold && (pt != prev_pt || prev_lc < threshold) {
+            tracing::warn!(
+                pt,
+                lc,
+                lc_max,
+                wall,
+                "HLC logical clock is close to saturation"
+            );
+        }
+    }
+}
+
+/// Records the drift violation.
+///
+/// Emits an event with both the incoming timestamp and the wall-clock time.
+#[inline]
+pub(crate) fn drift_violation(
+    peer: u64,
+    incoming_pt: i64,
+    wall: i64,
+    skew: i64,
+    max: usize,
+    policy: DriftPolicy,
+) {
+    #[cfg(any(feature = "metrics", feature = "tracing"))]
+    let direction = if skew < 0 { "behind" } else { "ahead" };
+    #[cfg(any(feature = "metrics", feature = "tracing"))]
+    let policy = match policy {
+        DriftPolicy::Reject => "reject",
+        DriftPolicy::Clamp => "clamp",
+        DriftPolicy::Report => "report",
+        DriftPolicy::Quarantine => "quarantine",
+    };
+
+    #[cfg(feature = "metrics")]
+    counter!(DRIFT_VIOLATIONS, "direction" => direction, "policy" => policy).increment(1);
+
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        peer,
+        incoming_pt,
+        wall,
+        skew,
+        max,
+        direction,
+        policy,
+        "HLC drift exceeded"
+    );
+}
+
+/// Watch for the wall-clock regressions.
+///
+/// Zero-sized, if neither the `metrics` nor the `tracing` feature is
+/// enabled.
+#[derive(Debug)]
+pub(crate) struct RegressionWatch {
+    /// Latest wall-clock time observed.
+    #[cfg(any(feature = "metrics", feature = "tracing"))]
+    latest: AtomicI64,
+}
+
+impl RegressionWatch {
+    /// Creates a new watch.
+    pub(crate) fn new() -> Self {
+        Self {
+            #[cfg(any(feature = "metrics", feature = "tracing"))]
+            latest: AtomicI64::new(i64::MIN),
+        }
+    }
+
+    /// Observes the wall-clock time, recording a regression, if it is behind
+    /// the latest time observed.
+    ///
+    /// Concurrent callers read the clock and record the readings in no
+    /// particular order, so an older reading may well arrive after a newer
+    /// one. Hence, the clock is read again (with `reread`), and a regression
+    /// is only recorded if the fresh reading is still behind the latest time
+    /// observed before it was taken.
+    ///
+    /// Emits an event with both the wall-clock times, and the physical time
+    /// of the generator (obtained with `pt`), which is kept until the
+    /// wall-clock catches up.
+    #[inline]
+    pub(crate) fn observe(
+        &self,
+        wall: i64,
+        reread: impl FnOnce() -> i64,
+        pt: impl FnOnce() -> i64,
+    ) {
+        #[cfg(any(feature = "metrics", feature = "tracing"))]
+        {
+            let latest = self.latest.fetch_max(wall, Ordering::AcqRel);
+            if latest <= wall {
+                return;
+            }
+            // Reading taken after `latest` was recorded is not behind it, unless the clock
+            // has actually gone backwards. Newer readings recorded in the meantime are
+            // irrelevant, hence the comparison with `latest` as seen before.
+            let wall = reread();
+            self.latest.fetch_max(wall, Ordering::AcqRel);
+            if latest > wall {
+                let regression = latest.saturating_sub(wall);
+
+                #[cfg(feature = "metrics")]
+                {
+                    counter!(CLOCK_REGRESSIONS).increment(1);
+                    histogram!(CLOCK_REGRESSION_MS).record(regression as f64);
+                }
+
+                #[cfg(feature = "tracing")]
+                tracing::warn!(
+                    latest,
+                    wall,
+                    regression,
+                    pt = pt(),
+                    "Wall-clock time went backwards, HLC physical time is kept"
+                );
+            }
+        }
+    }
+}
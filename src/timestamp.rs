@@ -1,34 +1,42 @@
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// Model-checked with loom, see `tests/loom.rs`.
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicU64, Ordering};
 use {
     crate::{
-        epoch::CustomEpochTimestamp,
+        encoding,
+        epoch::{CustomEpochTimestamp, DefaultEpoch, Epoch},
         error::{HlcError, HlcResult},
+        layout::{DefaultLayout, Layout},
+        telemetry,
     },
+    chrono::{DateTime, SecondsFormat, TimeDelta, Utc},
     std::{
+        marker::PhantomData,
         ops::{Add, AddAssign, Sub, SubAssign},
-        sync::atomic::{AtomicU64, Ordering},
+        str::FromStr,
+        time::{Duration, SystemTime},
     },
 };
 
-/// Number of bits to represent physical time in milliseconds since custom
-/// epoch.
-static PT_BITS: u8 = 42;
-
-/// Maximum value for physical time.
-static PT_MAX: u64 = (1 << PT_BITS) - 1;
-
-/// Number of bits to represent logical clock counter.
-static LC_BITS: u8 = 22;
-
-/// Maximum value for logical clock.
-static LC_MAX: u64 = (1 << LC_BITS) - 1;
+/// Hybrid logical clock (HLC) timestamp using the default layout and epoch.
+///
+/// See [`HlcTimestampOf`] for details.
+pub type HlcTimestamp = HlcTimestampOf<DefaultLayout, DefaultEpoch>;
 
 /// Hybrid logical clock (HLC) timestamp.
 ///
 /// This is a wrapper around raw `u64` data of HLC atomic timestamp.
 ///
-/// The timestamp is represented as a 64-bit unsigned integer. The upper 42 bits
-/// represent the physical time in milliseconds since a custom epoch, and the
-/// lower 22 bits represent the logical clock count.
+/// The timestamp is represented as a 64-bit unsigned integer. The upper
+/// [`L::PT_BITS`](Layout::PT_BITS) bits (42 by default) represent the physical
+/// time in milliseconds since the epoch `E` (2024-01-01 by default), the next
+/// [`L::LC_BITS`](Layout::LC_BITS) bits (22 by default) represent the logical
+/// clock count, and the lowest [`L::NODE_BITS`](Layout::NODE_BITS) bits (none
+/// by default) represent the identifier of the node that generated the
+/// timestamp.
 ///
 /// Normally, you don't need to worry about the details of the representation.
 ///
@@ -42,87 +50,310 @@ static LC_MAX: u64 = (1 << LC_BITS) - 1;
 ///
 /// Alternatively, rely on [`timestamp()`](Self::timestamp()) and
 /// [`count()`](Self::count()) methods to get the physical time and logical
-/// clock count.
+/// clock count. The node identifier is available via
+/// [`node()`](Self::node()).
 ///
 /// Finally, you can use the [`as_u64()`](Self::as_u64()) method to get the raw
 /// data, which is guaranteed to be monotonically increasing and capturing the
 /// happens-before relationship.
+///
+/// Timestamps have two string forms, both of which are accepted by
+/// [`FromStr`]:
+/// - human-readable (`{}`): RFC 3339 date-time with millisecond precision and
+///   the logical clock count, e.g. `2024-05-01T12:00:00.123Z#42` (with
+///   `@<node>` appended, if the layout reserves bits for node identifier). If
+///   the physical time is beyond the range of the date-time, it is written as a
+///   Unix timestamp in milliseconds instead.
+/// - canonical (`{:#}`, or [`encode()`](HlcTimestampOf::encode)): the raw value
+///   as 13 characters of Crockford's base32, e.g. `0173D5AFC001A`. This form is
+///   compact and lexicographically sortable.
+///
+/// ```
+/// use hlc_gen::HlcTimestamp;
+///
+/// let ts = HlcTimestamp::from_parts(1_714_564_800_123, 42).unwrap();
+/// assert_eq!(ts.to_string(), "2024-05-01T12:00:00.123Z#42");
+/// assert_eq!(format!("{ts:#}"), "0173D5AFC001A");
+/// assert_eq!(ts.to_string().parse::<HlcTimestamp>(), Ok(ts));
+/// assert_eq!(ts.encode().parse::<HlcTimestamp>(), Ok(ts));
+/// ```
+///
+/// With the `serde` feature enabled, timestamps are serialized as a
+/// `{timestamp, count}` structure (with `node` added, if the layout reserves
+/// bits for it) in human-readable formats, and as the raw `u64` value in binary
+/// formats. Deserialized values are validated.
+///
+/// Most of the time, the [`HlcTimestamp`] alias (which uses the
+/// [`DefaultLayout`] and [`DefaultEpoch`]) is all you need.
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct HlcTimestamp(u64);
+pub struct HlcTimestampOf<L: Layout = DefaultLayout, E: Epoch = DefaultEpoch>(
+    u64,
+    PhantomData<(L, E)>,
+);
 
-impl std::fmt::Display for HlcTimestamp {
+impl<L: Layout, E: Epoch> std::fmt::Display for HlcTimestampOf<L, E> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "HlcTimestamp {{ timestamp: {}, count: {} }}",
-            self.timestamp(),
-            self.count()
-        )
+        if f.alternate() {
+            let encoded = encoding::encode(self.0);
+            // Alphabet is ASCII-only, so the encoded value is a valid UTF-8.
+            return f.write_str(std::str::from_utf8(&encoded).map_err(|_| std::fmt::Error)?);
+        }
+
+        match DateTime::<Utc>::from_timestamp_millis(self.timestamp()) {
+            Some(dt) => write!(f, "{}", dt.to_rfc3339_opts(SecondsFormat::Millis, true))?,
+            None => write!(f, "{}", self.timestamp())?,
+        }
+        write!(f, "#{}", self.count())?;
+        if L::NODE_BITS > 0 {
+            write!(f, "@{}", self.node())?;
+        }
+        Ok(())
+    }
+}
+
+impl<L: Layout, E: Epoch> FromStr for HlcTimestampOf<L, E> {
+    type Err = HlcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((dt, rest)) = s.split_once('#') else {
+            return Self::try_from(encoding::decode(s)?);
+        };
+
+        // Physical time beyond the range of the date-time is written as is.
+        let pt = match dt.parse::<i64>() {
+            Ok(pt) => pt,
+            Err(_) => {
+                let dt = DateTime::parse_from_rfc3339(dt).map_err(HlcError::InvalidDateTime)?;
+                if dt.timestamp_subsec_nanos() % 1_000_000 != 0 {
+                    return Err(HlcError::InvalidFormat(
+                        "date-time precision finer than milliseconds".into(),
+                    ));
+                }
+                dt.timestamp_millis()
+            }
+        };
+        let (count, node) = match rest.split_once('@') {
+            Some((count, node)) => (count, node.parse().map_err(HlcError::InvalidNumber)?),
+            None => (rest, 0),
+        };
+        let count = count.parse().map_err(HlcError::InvalidNumber)?;
+
+        Self::from_parts_with_node(pt, count, node)
     }
 }
 
-impl TryFrom<u64> for HlcTimestamp {
+impl<L: Layout, E: Epoch> TryFrom<u64> for HlcTimestampOf<L, E> {
     type Error = HlcError;
 
     fn try_from(value: u64) -> Result<Self, Self::Error> {
-        let pt = (value >> LC_BITS) & PT_MAX;
-        let lc = value & LC_MAX;
-        Self::from_parts(CustomEpochTimestamp::to_unix_timestamp(pt), lc)
+        // Bits not covered by the layout must not be set.
+        let used_bits = Self::PT_SHIFT as u32 + L::PT_BITS as u32;
+        if value.checked_shr(used_bits).unwrap_or(0) != 0 {
+            return Err(HlcError::OutOfRangeTimestamp);
+        }
+
+        let (pt, lc) = Self::split_raw(value);
+        Self::from_parts_with_node(
+            CustomEpochTimestamp::<E>::to_unix_timestamp(pt),
+            lc,
+            value & Self::NODE_MAX,
+        )
     }
 }
 
 macro_rules! impl_sub {
     ($lhs:ty, $rhs:ty) => {
-        impl Sub<$rhs> for $lhs {
+        impl<L: Layout, E: Epoch> Sub<$rhs> for $lhs {
             type Output = i64;
 
             fn sub(self, rhs: $rhs) -> Self::Output {
-                let pt1 = ((self.0 >> LC_BITS) & PT_MAX) as i64;
-                let pt2 = ((rhs.0 >> LC_BITS) & PT_MAX) as i64;
+                let pt1 = self.split().0 as i64;
+                let pt2 = rhs.split().0 as i64;
                 pt1 - pt2
             }
         }
     };
 }
 
-impl_sub!(HlcTimestamp, HlcTimestamp);
-impl_sub!(&HlcTimestamp, &HlcTimestamp);
-impl_sub!(HlcTimestamp, &HlcTimestamp);
-impl_sub!(&HlcTimestamp, HlcTimestamp);
+impl_sub!(HlcTimestampOf<L, E>, HlcTimestampOf<L, E>);
+impl_sub!(&HlcTimestampOf<L, E>, &HlcTimestampOf<L, E>);
+impl_sub!(HlcTimestampOf<L, E>, &HlcTimestampOf<L, E>);
+impl_sub!(&HlcTimestampOf<L, E>, HlcTimestampOf<L, E>);
 
-impl Sub<u64> for HlcTimestamp {
+impl<L: Layout, E: Epoch> Sub<u64> for HlcTimestampOf<L, E> {
     type Output = Self;
 
     fn sub(self, ts: u64) -> Self::Output {
+        debug_assert!(
+            self.checked_sub(ts).is_ok(),
+            "HLC timestamp underflow: {self:?} - {ts}ms"
+        );
         let (pt, lc) = self.split();
-        Self((pt.wrapping_sub(ts) << LC_BITS) | lc)
+        Self::from_raw_parts(pt.wrapping_sub(ts), lc, self.node())
     }
 }
 
-impl SubAssign<u64> for HlcTimestamp {
+impl<L: Layout, E: Epoch> SubAssign<u64> for HlcTimestampOf<L, E> {
     fn sub_assign(&mut self, ts: u64) {
+        debug_assert!(
+            self.checked_sub(ts).is_ok(),
+            "HLC timestamp underflow: {self:?} - {ts}ms"
+        );
         let (pt, lc) = self.split();
-        self.0 = (pt.wrapping_sub(ts) << LC_BITS) | lc;
+        *self = Self::from_raw_parts(pt.wrapping_sub(ts), lc, self.node());
     }
 }
 
-impl Add<u64> for HlcTimestamp {
+impl<L: Layout, E: Epoch> Add<u64> for HlcTimestampOf<L, E> {
     type Output = Self;
 
     fn add(self, ts: u64) -> Self::Output {
+        debug_assert!(
+            self.checked_add(ts).is_ok(),
+            "HLC timestamp overflow: {self:?} + {ts}ms"
+        );
         let (pt, lc) = self.split();
-        Self((pt.wrapping_add(ts) << LC_BITS) | lc)
+        Self::from_raw_parts(pt.wrapping_add(ts), lc, self.node())
     }
 }
 
-impl AddAssign<u64> for HlcTimestamp {
+impl<L: Layout, E: Epoch> AddAssign<u64> for HlcTimestampOf<L, E> {
     fn add_assign(&mut self, ts: u64) {
+        debug_assert!(
+            self.checked_add(ts).is_ok(),
+            "HLC timestamp overflow: {self:?} + {ts}ms"
+        );
         let (pt, lc) = self.split();
-        self.0 = (pt.wrapping_add(ts) << LC_BITS) | lc;
+        *self = Self::from_raw_parts(pt.wrapping_add(ts), lc, self.node());
     }
 }
 
-impl HlcTimestamp {
+impl<L: Layout, E: Epoch> Add<Duration> for HlcTimestampOf<L, E> {
+    type Output = Self;
+
+    fn add(self, duration: Duration) -> Self::Output {
+        self + duration_millis(duration)
+    }
+}
+
+impl<L: Layout, E: Epoch> AddAssign<Duration> for HlcTimestampOf<L, E> {
+    fn add_assign(&mut self, duration: Duration) {
+        *self += duration_millis(duration);
+    }
+}
+
+impl<L: Layout, E: Epoch> Sub<Duration> for HlcTimestampOf<L, E> {
+    type Output = Self;
+
+    fn sub(self, duration: Duration) -> Self::Output {
+        self - duration_millis(duration)
+    }
+}
+
+impl<L: Layout, E: Epoch> SubAssign<Duration> for HlcTimestampOf<L, E> {
+    fn sub_assign(&mut self, duration: Duration) {
+        *self -= duration_millis(duration);
+    }
+}
+
+impl<L: Layout, E: Epoch> TryFrom<HlcTimestampOf<L, E>> for SystemTime {
+    type Error = HlcError;
+
+    /// Converts the physical time of the timestamp (the logical clock count
+    /// is dropped).
+    fn try_from(ts: HlcTimestampOf<L, E>) -> Result<Self, Self::Error> {
+        let millis = ts.timestamp();
+        let offset = Duration::from_millis(millis.unsigned_abs());
+        if millis >= 0 {
+            SystemTime::UNIX_EPOCH.checked_add(offset)
+        } else {
+            SystemTime::UNIX_EPOCH.checked_sub(offset)
+        }
+        .ok_or(HlcError::OutOfRangeTimestamp)
+    }
+}
+
+impl<L: Layout, E: Epoch> TryFrom<SystemTime> for HlcTimestampOf<L, E> {
+    type Error = HlcError;
+
+    /// Creates a timestamp with zero logical clock count (sub-millisecond
+    /// precision is truncated).
+    fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+        let millis = match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(d) => i64::try_from(d.as_millis()).map_err(|_| HlcError::OutOfRangeTimestamp)?,
+            Err(e) => {
+                // Round towards negative infinity, as for the times after the Unix epoch.
+                let d = e.duration();
+                let millis =
+                    i64::try_from(d.as_millis() + u128::from(d.subsec_nanos() % 1_000_000 > 0))
+                        .map_err(|_| HlcError::OutOfRangeTimestamp)?;
+                -millis
+            }
+        };
+        Self::new(millis)
+    }
+}
+
+impl<L: Layout, E: Epoch> TryFrom<HlcTimestampOf<L, E>> for DateTime<Utc> {
+    type Error = HlcError;
+
+    /// Converts the physical time of the timestamp (the logical clock count
+    /// is dropped).
+    fn try_from(ts: HlcTimestampOf<L, E>) -> Result<Self, Self::Error> {
+        DateTime::from_timestamp_millis(ts.timestamp()).ok_or(HlcError::OutOfRangeTimestamp)
+    }
+}
+
+impl<L: Layout, E: Epoch> TryFrom<DateTime<Utc>> for HlcTimestampOf<L, E> {
+    type Error = HlcError;
+
+    /// Creates a timestamp with zero logical clock count (sub-millisecond
+    /// precision is truncated).
+    fn try_from(dt: DateTime<Utc>) -> Result<Self, Self::Error> {
+        Self::new(dt.timestamp_millis())
+    }
+}
+
+impl<L: Layout, E: Epoch> HlcTimestampOf<L, E> {
+    /// Maximum value for physical time (in milliseconds since custom epoch).
+    ///
+    /// Layouts, which do not fit, are rejected at compile time:
+    ///
+    /// ```compile_fail
+    /// use hlc_gen::{HlcTimestampOf, layout::BitLayout};
+    ///
+    /// // Maximum physical time is beyond `i64::MAX` since the default epoch.
+    /// let _ = HlcTimestampOf::<BitLayout<63, 1>>::try_from(u64::MAX);
+    /// ```
+    pub const PT_MAX: u64 = {
+        assert!(
+            L::PT_BITS > 0
+                && L::LC_BITS > 0
+                && L::PT_BITS as u32 + L::LC_BITS as u32 + L::NODE_BITS as u32 <= 64,
+            "invalid HLC layout: time and counter must be non-empty and all parts must fit into \
+             64 bits"
+        );
+        let pt_max = (1 << L::PT_BITS) - 1;
+        assert!(
+            E::UNIX_MILLIS.checked_add_unsigned(pt_max).is_some(),
+            "invalid HLC layout: maximum physical time must fit into i64 Unix timestamp"
+        );
+        pt_max
+    };
+
+    /// Maximum value for logical clock.
+    pub const LC_MAX: u64 = (1 << L::LC_BITS) - 1;
+
+    /// Maximum value for node identifier (zero, if the layout reserves no bits
+    /// for it).
+    pub const NODE_MAX: u64 = (1 << L::NODE_BITS) - 1;
+
+    /// Offset of the logical clock bits.
+    const LC_SHIFT: u8 = L::NODE_BITS;
+
+    /// Offset of the physical time bits.
+    const PT_SHIFT: u8 = L::LC_BITS + L::NODE_BITS;
+
     /// Creates a new HLC timestamp from incoming physical time.
     pub fn new(unix_timestamp: i64) -> HlcResult<Self> {
         Self::from_parts(unix_timestamp, 0)
@@ -131,28 +362,53 @@ impl HlcTimestamp {
     /// Creates a new HLC timestamp from the given physical time and logical
     /// clock count.
     pub fn from_parts(pt: i64, lc: u64) -> HlcResult<Self> {
-        if pt > PT_MAX as i64 {
-            return Err(HlcError::PhysicalTimeExceedsMax(pt, PT_MAX));
-        }
-        if lc > LC_MAX {
-            return Err(HlcError::LogicalClockExceedsMax(lc, LC_MAX));
-        }
+        Self::from_parts_with_node(pt, lc, 0)
+    }
 
+    /// Creates a new HLC timestamp from the given physical time, logical
+    /// clock count and node identifier.
+    pub fn from_parts_with_node(pt: i64, lc: u64, node: u64) -> HlcResult<Self> {
         // Convert the physical time to milliseconds since the custom epoch.
-        let ts = CustomEpochTimestamp::from_unix_timestamp(pt)?;
+        let ts = CustomEpochTimestamp::<E>::from_unix_timestamp(pt)?;
 
-        let combined = (ts.millis() << LC_BITS) | lc;
-        Ok(Self(combined))
+        if ts.millis() > Self::PT_MAX {
+            let max = CustomEpochTimestamp::<E>::to_unix_timestamp(Self::PT_MAX);
+            return Err(HlcError::PhysicalTimeExceedsMax(pt, max));
+        }
+        if lc > Self::LC_MAX {
+            return Err(HlcError::LogicalClockExceedsMax(lc, Self::LC_MAX));
+        }
+        if node > Self::NODE_MAX {
+            return Err(HlcError::NodeIdExceedsMax(node, Self::NODE_MAX));
+        }
+
+        Ok(Self::from_raw_parts(ts.millis(), lc, node))
     }
 
     /// Unix timestamp in milliseconds.
     pub fn timestamp(&self) -> i64 {
-        CustomEpochTimestamp::to_unix_timestamp((self.0 >> LC_BITS) & PT_MAX)
+        CustomEpochTimestamp::<E>::to_unix_timestamp(self.split().0)
     }
 
     /// Logical clock count.
     pub fn count(&self) -> u64 {
-        self.0 & LC_MAX
+        self.split().1
+    }
+
+    /// Identifier of the node that generated the timestamp.
+    ///
+    /// Always zero, if the layout reserves no bits for node identifiers.
+    pub fn node(&self) -> u64 {
+        self.0 & Self::NODE_MAX
+    }
+
+    /// Returns a copy of the timestamp with the node identifier replaced.
+    pub fn with_node(&self, node: u64) -> HlcResult<Self> {
+        if node > Self::NODE_MAX {
+            return Err(HlcError::NodeIdExceedsMax(node, Self::NODE_MAX));
+        }
+        let (pt, lc) = self.split();
+        Ok(Self::from_raw_parts(pt, lc, node))
     }
 
     /// Returns the physical time and logical clock count as a tuple.
@@ -165,77 +421,273 @@ impl HlcTimestamp {
         self.0
     }
 
+    /// Canonical string form of the timestamp (same as `{:#}`), see
+    /// [`HlcTimestampOf`] for details.
+    pub fn encode(&self) -> String {
+        format!("{self:#}")
+    }
+
+    /// Re-encodes the timestamp against another epoch.
+    ///
+    /// Fails if the physical time cannot be represented in the target epoch,
+    /// i.e. it either precedes the target epoch or exceeds its range.
+    pub fn to_epoch<E2: Epoch>(&self) -> HlcResult<HlcTimestampOf<L, E2>> {
+        HlcTimestampOf::from_parts_with_node(self.timestamp(), self.count(), self.node())
+    }
+
+    /// Adds `millis` milliseconds to the physical time.
+    ///
+    /// Unlike the `+` operator (which wraps around), fails if the result
+    /// exceeds the range of the layout and epoch.
+    pub fn checked_add(&self, millis: u64) -> HlcResult<Self> {
+        let (pt, lc) = self.split();
+        match pt.checked_add(millis) {
+            Some(pt) if pt <= Self::PT_MAX => Ok(Self::from_raw_parts(pt, lc, self.node())),
+            _ => Err(HlcError::PhysicalTimeExceedsMax(
+                self.timestamp().saturating_add_unsigned(millis),
+                CustomEpochTimestamp::<E>::to_unix_timestamp(Self::PT_MAX),
+            )),
+        }
+    }
+
+    /// Subtracts `millis` milliseconds from the physical time.
+    ///
+    /// Unlike the `-` operator (which wraps around), fails if the result
+    /// precedes the epoch.
+    pub fn checked_sub(&self, millis: u64) -> HlcResult<Self> {
+        let (pt, lc) = self.split();
+        match pt.checked_sub(millis) {
+            Some(pt) => Ok(Self::from_raw_parts(pt, lc, self.node())),
+            None => Err(HlcError::TimestampBelowMin(
+                self.timestamp().saturating_sub_unsigned(millis),
+                E::UNIX_MILLIS,
+            )),
+        }
+    }
+
+    /// Adds `millis` milliseconds to the physical time, saturating at the
+    /// maximum physical time of the layout.
+    pub fn saturating_add(&self, millis: u64) -> Self {
+        let (pt, lc) = self.split();
+        Self::from_raw_parts(pt.saturating_add(millis).min(Self::PT_MAX), lc, self.node())
+    }
+
+    /// Subtracts `millis` milliseconds from the physical time, saturating at
+    /// the epoch.
+    pub fn saturating_sub(&self, millis: u64) -> Self {
+        let (pt, lc) = self.split();
+        Self::from_raw_parts(pt.saturating_sub(millis), lc, self.node())
+    }
+
+    /// Same as [`checked_add()`](Self::checked_add), but takes a
+    /// [`Duration`] (truncated to milliseconds).
+    pub fn checked_add_duration(&self, duration: Duration) -> HlcResult<Self> {
+        self.checked_add(duration_millis(duration))
+    }
+
+    /// Same as [`checked_sub()`](Self::checked_sub), but takes a
+    /// [`Duration`] (truncated to milliseconds).
+    pub fn checked_sub_duration(&self, duration: Duration) -> HlcResult<Self> {
+        self.checked_sub(duration_millis(duration))
+    }
+
+    /// Same as [`saturating_add()`](Self::saturating_add), but takes a
+    /// [`Duration`] (truncated to milliseconds).
+    pub fn saturating_add_duration(&self, duration: Duration) -> Self {
+        self.saturating_add(duration_millis(duration))
+    }
+
+    /// Same as [`saturating_sub()`](Self::saturating_sub), but takes a
+    /// [`Duration`] (truncated to milliseconds).
+    pub fn saturating_sub_duration(&self, duration: Duration) -> Self {
+        self.saturating_sub(duration_millis(duration))
+    }
+
+    /// Time elapsed from the `earlier` timestamp to this one (zero, if
+    /// `earlier` is actually later).
+    ///
+    /// Only physical time is taken into account.
+    pub fn duration_since(&self, earlier: &Self) -> Duration {
+        let (pt, earlier_pt) = (self.split().0, earlier.split().0);
+        Duration::from_millis(pt.saturating_sub(earlier_pt))
+    }
+
+    /// Signed time difference between this timestamp and the `other` one
+    /// (positive, if `other` is earlier).
+    ///
+    /// Only physical time is taken into account.
+    pub fn signed_duration_since(&self, other: &Self) -> TimeDelta {
+        TimeDelta::milliseconds(self - other)
+    }
+
     /// Returns *raw* physical time and logical clock count parts.
     fn split(&self) -> (u64, u64) {
-        let pt = (self.0 >> LC_BITS) & PT_MAX;
-        let lc = self.0 & LC_MAX;
+        Self::split_raw(self.0)
+    }
+
+    /// Splits raw `u64` data into physical time and logical clock count parts.
+    fn split_raw(value: u64) -> (u64, u64) {
+        let pt = (value >> Self::PT_SHIFT) & Self::PT_MAX;
+        let lc = (value >> Self::LC_SHIFT) & Self::LC_MAX;
         (pt, lc)
     }
+
+    /// Creates a timestamp from *raw* physical time (in milliseconds since
+    /// custom epoch), logical clock count and node identifier parts.
+    pub(crate) fn from_raw_parts(pt: u64, lc: u64, node: u64) -> Self {
+        Self(
+            (pt << Self::PT_SHIFT) | (lc << Self::LC_SHIFT) | node,
+            PhantomData,
+        )
+    }
+}
+
+/// Whole milliseconds of the duration, saturating at `u64::MAX`.
+fn duration_millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+/// Range of consecutive HLC timestamps.
+///
+/// Produced by
+/// [`HlcGenerator::next_timestamps()`](crate::HlcGenerator::next_timestamps),
+/// yields strictly increasing timestamps. Once the logical clock reaches its
+/// maximum, the range spills over into the next millisecond.
+#[derive(Debug, Clone)]
+pub struct HlcTimestampRange<L: Layout = DefaultLayout, E: Epoch = DefaultEpoch> {
+    /// Raw value of the next timestamp to yield.
+    next: u64,
+
+    /// Number of timestamps left to yield.
+    remaining: u64,
+
+    _marker: PhantomData<(L, E)>,
+}
+
+impl<L: Layout, E: Epoch> HlcTimestampRange<L, E> {
+    /// Creates a range of `len` timestamps, the last of which is `last`.
+    pub(crate) fn ending_at(last: HlcTimestampOf<L, E>, len: u64) -> Self {
+        let span = len.saturating_sub(1) << HlcTimestampOf::<L, E>::LC_SHIFT;
+        Self {
+            next: last.0 - span,
+            remaining: len,
+            _marker: PhantomData,
+        }
+    }
+
+    /// First timestamp of the range (if the range is not empty).
+    pub fn start(&self) -> Option<HlcTimestampOf<L, E>> {
+        (self.remaining > 0).then_some(HlcTimestampOf(self.next, PhantomData))
+    }
+
+    /// Last timestamp of the range, inclusive (if the range is not empty).
+    pub fn end(&self) -> Option<HlcTimestampOf<L, E>> {
+        self.remaining.checked_sub(1).map(|span| {
+            HlcTimestampOf(
+                self.next + (span << HlcTimestampOf::<L, E>::LC_SHIFT),
+                PhantomData,
+            )
+        })
+    }
+}
+
+impl<L: Layout, E: Epoch> Iterator for HlcTimestampRange<L, E> {
+    type Item = HlcTimestampOf<L, E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let ts = self.start()?;
+        self.remaining -= 1;
+        // Incrementing the logical clock bits carries over into the physical time
+        // bits, so no special handling of spill-over is needed.
+        self.next = self
+            .next
+            .wrapping_add(1 << HlcTimestampOf::<L, E>::LC_SHIFT);
+        Some(ts)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = usize::try_from(self.remaining).unwrap_or(usize::MAX);
+        (len, usize::try_from(self.remaining).ok())
+    }
 }
 
+impl<L: Layout, E: Epoch> ExactSizeIterator for HlcTimestampRange<L, E> {}
+
 #[derive(Debug)]
-pub struct HlcAtomicTimestamp(AtomicU64);
+pub struct HlcAtomicTimestamp<L: Layout = DefaultLayout, E: Epoch = DefaultEpoch>(
+    AtomicU64,
+    PhantomData<(L, E)>,
+);
 
-impl From<HlcTimestamp> for HlcAtomicTimestamp {
-    fn from(ts: HlcTimestamp) -> Self {
-        Self(AtomicU64::new(ts.0))
+impl<L: Layout, E: Epoch> From<HlcTimestampOf<L, E>> for HlcAtomicTimestamp<L, E> {
+    fn from(ts: HlcTimestampOf<L, E>) -> Self {
+        Self(AtomicU64::new(ts.0), PhantomData)
     }
 }
 
-impl HlcAtomicTimestamp {
+impl<L: Layout, E: Epoch> HlcAtomicTimestamp<L, E> {
     /// Sets the physical time and logical clock count.
     ///
     /// Expected closure gets the current physical time and logical clock count
     /// at the moment of the call and must return the new values for both.
     ///
     /// This is an atomic operation that ensures thread safety in a lock-free
-    /// fashion. Either both values are updated or none are.
+    /// fashion. Either both values are updated or none are. The node
+    /// identifier is never changed.
     pub fn update<F>(&self, new_values: F) -> HlcResult<Self>
     where
         F: Fn(i64, u64) -> HlcResult<(i64, u64)>,
     {
+        let mut retries = 0;
         loop {
             let current = self.0.load(Ordering::Acquire);
+            let (current_pt, current_lc) = HlcTimestampOf::<L, E>::split_raw(current);
 
             // Obtain new values for physical time and logical clock count.
             let (pt, lc) = new_values(
-                CustomEpochTimestamp::to_unix_timestamp((current >> LC_BITS) & PT_MAX),
-                current & LC_MAX,
+                CustomEpochTimestamp::<E>::to_unix_timestamp(current_pt),
+                current_lc,
             )?;
 
-            if pt > PT_MAX as i64 {
-                return Err(HlcError::PhysicalTimeExceedsMax(pt, PT_MAX));
-            }
-            if lc > LC_MAX {
-                return Err(HlcError::LogicalClockExceedsMax(lc, LC_MAX));
-            }
-
-            let ts = CustomEpochTimestamp::from_unix_timestamp(pt)?;
-            let new_combined = (ts.millis() << LC_BITS) | lc;
+            let node = current & HlcTimestampOf::<L, E>::NODE_MAX;
+            let new_combined = HlcTimestampOf::<L, E>::from_parts_with_node(pt, lc, node)?.0;
 
             if self
                 .0
                 .compare_exchange(current, new_combined, Ordering::AcqRel, Ordering::Acquire)
                 .is_ok()
             {
-                return Ok(Self(AtomicU64::new(new_combined)));
+                telemetry::cas_retries(retries);
+                return Ok(Self(AtomicU64::new(new_combined), PhantomData));
             }
+            retries += 1;
         }
     }
 
     /// Creates a new HLC timestamp snapshot.
-    pub fn snapshot(&self) -> HlcTimestamp {
-        HlcTimestamp(self.0.load(Ordering::Acquire))
+    pub fn snapshot(&self) -> HlcTimestampOf<L, E> {
+        HlcTimestampOf(self.0.load(Ordering::Acquire), PhantomData)
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, not(loom)))]
 mod tests {
-    use {super::*, crate::epoch::EPOCH, chrono::Utc, std::sync::Arc};
+    use {
+        super::*,
+        crate::{
+            epoch::{EPOCH, EpochAt, UnixEpoch},
+            layout::BitLayout,
+        },
+        chrono::Utc,
+        std::sync::Arc,
+    };
 
     #[test]
     fn concurrent_updates_to_atomic_timestamp() {
-        let timestamp = Arc::new(HlcAtomicTimestamp(AtomicU64::new(0)));
+        let timestamp = Arc::new(HlcAtomicTimestamp::<DefaultLayout>(
+            AtomicU64::new(0),
+            PhantomData,
+        ));
 
         // Create multiple threads to update the timestamp concurrently.
         let mut handles = vec![];
@@ -265,6 +717,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::op_ref)]
     fn arithmetics() {
         let start = Utc::now().timestamp_millis();
         let t1 = HlcTimestamp::from_parts(start, 123).unwrap();
@@ -298,4 +751,292 @@ mod tests {
         assert_eq!(t2 - &t1, 1000i64);
         assert_eq!(t1 - &t2, -1000i64);
     }
+
+    #[test]
+    fn custom_layout() {
+        type Ts = HlcTimestampOf<BitLayout<48, 16>>;
+        assert_eq!(Ts::PT_MAX, (1 << 48) - 1);
+        assert_eq!(Ts::LC_MAX, u16::MAX as u64);
+
+        let t1 = Ts::from_parts(EPOCH + 12345, 678).unwrap();
+        assert_eq!(t1.parts(), (EPOCH + 12345, 678));
+        assert_eq!(t1.as_u64(), (12345 << 16) | 678);
+        assert_eq!(Ts::try_from(t1.as_u64()), Ok(t1));
+
+        let t2 = t1 + 1000;
+        assert_eq!(t2.parts(), (EPOCH + 13345, 678));
+
+        // Layout covering less than 64 bits rejects values with unused bits set.
+        type Short = HlcTimestampOf<BitLayout<40, 20>>;
+        assert!(Short::try_from((1 << 60) - 1).is_ok());
+        assert_eq!(Short::try_from(1 << 60), Err(HlcError::OutOfRangeTimestamp));
+        assert_eq!(t2 - t1, 1000);
+
+        assert_eq!(
+            Ts::from_parts(EPOCH, 1 << 16),
+            Err(HlcError::LogicalClockExceedsMax(1 << 16, Ts::LC_MAX))
+        );
+    }
+
+    #[test]
+    fn extreme_layout() {
+        // Widest physical time fitting into `i64` along with the default epoch.
+        type Ts = HlcTimestampOf<BitLayout<62, 1>>;
+        let max = Ts::try_from((1 << 63) - 1).unwrap();
+        assert_eq!(max.parts(), (EPOCH + (1 << 62) - 1, 1));
+        assert_eq!(
+            max.checked_add(1),
+            Err(HlcError::PhysicalTimeExceedsMax(
+                EPOCH + (1 << 62),
+                EPOCH + (1 << 62) - 1
+            ))
+        );
+
+        // All 63 bits fit, if the epoch is early enough.
+        type Early = HlcTimestampOf<BitLayout<63, 1>, EpochAt<{ i64::MIN }>>;
+        let max = Early::try_from(u64::MAX).unwrap();
+        assert_eq!(max.parts(), (-1, 1));
+        assert_eq!(Early::new(i64::MIN).unwrap().as_u64(), 0);
+        assert_eq!(Early::new(0), Err(HlcError::PhysicalTimeExceedsMax(0, -1)));
+    }
+
+    #[test]
+    fn custom_epoch() {
+        // 2019-01-01 00:00:00 UTC
+        type Ts = HlcTimestampOf<DefaultLayout, EpochAt<1_546_300_800_000>>;
+        let unix_ts = 1_556_668_800_000; // 2019-05-01 00:00:00 UTC
+
+        // Default epoch cannot represent timestamps from 2019.
+        assert_eq!(
+            HlcTimestamp::from_parts(unix_ts, 42),
+            Err(HlcError::TimestampBelowMin(unix_ts, EPOCH))
+        );
+
+        let t1 = Ts::from_parts(unix_ts, 42).unwrap();
+        assert_eq!(t1.parts(), (unix_ts, 42));
+        assert_eq!(Ts::try_from(t1.as_u64()), Ok(t1));
+
+        // Re-encoding against an older epoch preserves the parts.
+        let t2 = t1.to_epoch::<UnixEpoch>().unwrap();
+        assert_eq!(t2.parts(), t1.parts());
+        assert_ne!(t2.as_u64(), t1.as_u64());
+
+        // Re-encoding against a later epoch is rejected.
+        assert_eq!(
+            t1.to_epoch::<DefaultEpoch>(),
+            Err(HlcError::TimestampBelowMin(unix_ts, EPOCH))
+        );
+    }
+
+    #[test]
+    fn node_id() {
+        type Ts = HlcTimestampOf<BitLayout<42, 12, 10>>;
+        assert_eq!(Ts::LC_MAX, (1 << 12) - 1);
+        assert_eq!(Ts::NODE_MAX, (1 << 10) - 1);
+        assert_eq!(HlcTimestamp::NODE_MAX, 0);
+
+        let t1 = Ts::from_parts_with_node(EPOCH + 12345, 67, 89).unwrap();
+        assert_eq!(t1.parts(), (EPOCH + 12345, 67));
+        assert_eq!(t1.node(), 89);
+        assert_eq!(t1.as_u64(), (12345 << 22) | (67 << 10) | 89);
+        assert_eq!(Ts::try_from(t1.as_u64()), Ok(t1));
+
+        // Node identifier is the tie-breaker, HLC parts take precedence.
+        let t2 = t1.with_node(90).unwrap();
+        assert!(t1 < t2);
+        let t3 = Ts::from_parts_with_node(EPOCH + 12345, 68, 0).unwrap();
+        assert!(t2 < t3);
+
+        // Arithmetic preserves node identifier.
+        let t4 = t1 + 1000;
+        assert_eq!(t4.parts(), (EPOCH + 13345, 67));
+        assert_eq!(t4.node(), 89);
+        assert_eq!(t4 - t1, 1000);
+
+        assert_eq!(
+            t1.with_node(1 << 10),
+            Err(HlcError::NodeIdExceedsMax(1 << 10, Ts::NODE_MAX))
+        );
+        assert_eq!(
+            HlcTimestamp::from_parts_with_node(EPOCH, 0, 1),
+            Err(HlcError::NodeIdExceedsMax(1, 0))
+        );
+    }
+
+    #[test]
+    fn checked_arithmetics() {
+        let t1 = HlcTimestamp::from_parts(EPOCH + 1000, 123).unwrap();
+        let max = HlcTimestamp::from_parts_with_node(
+            EPOCH + HlcTimestamp::PT_MAX as i64,
+            HlcTimestamp::LC_MAX,
+            0,
+        )
+        .unwrap();
+
+        assert_eq!(t1.checked_add(1000).unwrap().parts(), (EPOCH + 2000, 123));
+        assert_eq!(t1.checked_sub(1000).unwrap().parts(), (EPOCH, 123));
+        assert_eq!(
+            t1.checked_sub(1001),
+            Err(HlcError::TimestampBelowMin(EPOCH - 1, EPOCH))
+        );
+        assert_eq!(
+            max.checked_add(1),
+            Err(HlcError::PhysicalTimeExceedsMax(
+                max.timestamp() + 1,
+                max.timestamp()
+            ))
+        );
+        assert!(t1.checked_add(u64::MAX).is_err());
+
+        assert_eq!(t1.saturating_sub(5000).parts(), (EPOCH, 123));
+        assert_eq!(t1.saturating_add(u64::MAX).parts(), (max.timestamp(), 123));
+        assert_eq!(max.saturating_add(1), max);
+
+        // Durations are truncated to milliseconds.
+        let d = Duration::from_micros(1_500_999);
+        assert_eq!(
+            t1.checked_add_duration(d).unwrap().timestamp(),
+            EPOCH + 2500
+        );
+        assert_eq!(
+            t1.checked_sub_duration(d).unwrap_err(),
+            t1.checked_sub(1500).unwrap_err()
+        );
+        assert_eq!(t1.saturating_add_duration(d).timestamp(), EPOCH + 2500);
+        assert_eq!(t1.saturating_sub_duration(d).timestamp(), EPOCH);
+
+        // Node identifier is preserved.
+        type Ts = HlcTimestampOf<BitLayout<42, 12, 10>>;
+        let t2 = Ts::from_parts_with_node(EPOCH + 1000, 1, 7).unwrap();
+        assert_eq!(t2.checked_add(1).unwrap().node(), 7);
+        assert_eq!(t2.saturating_sub(2000).node(), 7);
+    }
+
+    #[test]
+    fn durations() {
+        let t1 = HlcTimestamp::from_parts(EPOCH + 1000, 5).unwrap();
+        let t2 = HlcTimestamp::from_parts(EPOCH + 3500, 0).unwrap();
+
+        assert_eq!(t2.duration_since(&t1), Duration::from_millis(2500));
+        assert_eq!(t1.duration_since(&t2), Duration::ZERO);
+        assert_eq!(t2.signed_duration_since(&t1), TimeDelta::milliseconds(2500));
+        assert_eq!(
+            t1.signed_duration_since(&t2),
+            TimeDelta::milliseconds(-2500)
+        );
+
+        let mut t3 = t1 + Duration::from_micros(2_500_900);
+        assert_eq!(t3.parts(), (EPOCH + 3500, 5));
+        t3 -= Duration::from_millis(2500);
+        assert_eq!(t3, t1);
+        t3 += Duration::from_secs(1);
+        assert_eq!(t3 - Duration::from_secs(1), t1);
+    }
+
+    #[test]
+    fn conversions() {
+        let ts = HlcTimestamp::from_parts(1_746_057_600_123, 42).unwrap();
+
+        let time = SystemTime::try_from(ts).unwrap();
+        assert_eq!(
+            time,
+            SystemTime::UNIX_EPOCH + Duration::from_millis(1_746_057_600_123)
+        );
+        let dt = DateTime::<Utc>::try_from(ts).unwrap();
+        assert_eq!(dt.timestamp_millis(), 1_746_057_600_123);
+
+        // Logical clock count is dropped, sub-millisecond precision truncated.
+        let back = HlcTimestamp::try_from(time + Duration::from_micros(999)).unwrap();
+        assert_eq!(back.parts(), (1_746_057_600_123, 0));
+        let back = HlcTimestamp::try_from(dt).unwrap();
+        assert_eq!(back.parts(), (1_746_057_600_123, 0));
+
+        // Times before the epoch are rejected.
+        assert_eq!(
+            HlcTimestamp::try_from(SystemTime::UNIX_EPOCH),
+            Err(HlcError::TimestampBelowMin(0, EPOCH))
+        );
+
+        // Times before the Unix epoch round towards negative infinity.
+        type Ts = HlcTimestampOf<DefaultLayout, EpochAt<-1000>>;
+        let before = SystemTime::UNIX_EPOCH - Duration::from_micros(1500);
+        assert_eq!(Ts::try_from(before).unwrap().timestamp(), -2);
+        let ts = Ts::from_parts(-2, 0).unwrap();
+        assert_eq!(
+            SystemTime::try_from(ts).unwrap(),
+            SystemTime::UNIX_EPOCH - Duration::from_millis(2)
+        );
+        assert_eq!(
+            DateTime::<Utc>::try_from(ts).unwrap().timestamp_millis(),
+            -2
+        );
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "HLC timestamp underflow")]
+    fn sub_below_epoch() {
+        let _ = HlcTimestamp::from_parts(EPOCH, 0).unwrap() - 1;
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "HLC timestamp overflow")]
+    fn add_beyond_max() {
+        let mut ts = HlcTimestamp::from_parts(EPOCH, 0).unwrap();
+        ts += HlcTimestamp::PT_MAX + 1;
+    }
+
+    #[test]
+    #[cfg(feature = "metrics")]
+    fn cas_retries_recorded() {
+        use {
+            metrics_util::debugging::{DebugValue, DebuggingRecorder},
+            std::cell::Cell,
+        };
+
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        metrics::with_local_recorder(&recorder, || {
+            let ts = HlcAtomicTimestamp::from(HlcTimestamp::from_parts(EPOCH, 0).unwrap());
+            // Another thread gets in the way of the first two attempts.
+            let interference = Cell::new(2);
+            ts.update(|pt, lc| {
+                if interference.get() > 0 {
+                    interference.set(interference.get() - 1);
+                    ts.0.fetch_add(1, Ordering::AcqRel);
+                }
+                Ok((pt, lc + 1))
+            })
+            .unwrap();
+        });
+
+        let metrics = snapshotter.snapshot().into_vec();
+        assert_eq!(metrics.len(), 1);
+        assert_eq!(metrics[0].0.key().name(), telemetry::CAS_RETRIES);
+        assert_eq!(metrics[0].3, DebugValue::Counter(2));
+    }
+
+    #[test]
+    fn range() {
+        type Ts = HlcTimestampOf<BitLayout<42, 2, 2>>;
+        let last = Ts::from_parts_with_node(EPOCH + 1, 1, 3).unwrap();
+        let range = HlcTimestampRange::ending_at(last, 4);
+        assert_eq!(range.len(), 4);
+        assert_eq!(range.start().unwrap().parts(), (EPOCH, 2));
+        assert_eq!(range.end(), Some(last));
+
+        // Spills over into the next millisecond, node identifier is preserved.
+        let timestamps = range.collect::<Vec<_>>();
+        assert_eq!(
+            timestamps.iter().map(|t| t.parts()).collect::<Vec<_>>(),
+            vec![(EPOCH, 2), (EPOCH, 3), (EPOCH + 1, 0), (EPOCH + 1, 1)]
+        );
+        assert!(timestamps.iter().all(|t| t.node() == 3));
+        assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+
+        let mut empty = HlcTimestampRange::ending_at(last, 0);
+        assert_eq!((empty.start(), empty.end(), empty.len()), (None, None, 0));
+        assert_eq!(empty.next(), None);
+    }
 }
@@ -2,3 +2,19 @@
 // HLC timestamps are using custom epoch, so incoming timestamps cannot be
 // smaller than this.
 pub const EPOCH: i64 = 1_704_067_200_000;
+
+/// Clock, which never goes backwards: every reading is a millisecond ahead of
+/// the previous one.
+///
+/// Yields after every reading, so other threads are likely to read (and
+/// observe) the clock before the reading is observed.
+#[allow(dead_code)]
+pub struct SteadyClock(pub std::sync::atomic::AtomicI64);
+
+impl hlc_gen::source::ClockSource for SteadyClock {
+    fn current_timestamp(&self) -> i64 {
+        let timestamp = self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        std::thread::yield_now();
+        timestamp
+    }
+}
@@ -2,7 +2,17 @@ mod common;
 
 use {
     common::EPOCH,
-    hlc_gen::{HlcGenerator, HlcTimestamp},
+    hlc_gen::{
+        HlcGenerator,
+        HlcGeneratorBuilder,
+        HlcTimestamp,
+        HlcTimestampOf,
+        config::{DriftPolicy, HlcConfig, OverflowPolicy},
+        error::{HlcError, HlcResult},
+        layout::BitLayout,
+        persist::{FilePersistence, Persistence},
+        source::{ClockSource, ManualClock},
+    },
     parking_lot::Mutex,
     std::{sync::Arc, time::Duration},
 };
@@ -59,7 +69,9 @@ fn max_drift() {
     assert_eq!(
         g.update(&t3),
         Err(hlc_gen::error::HlcError::DriftTooLarge(
-            max_drift as usize + 1,
+            EPOCH + 12345 + max_drift + 1,
+            EPOCH + 12345,
+            max_drift + 1,
             max_drift as usize
         ))
     );
@@ -73,6 +85,657 @@ fn max_drift() {
     );
 }
 
+#[test]
+fn max_lag() {
+    let g = HlcGenerator::builder()
+        .clock(ManualClock::new(EPOCH + 10_000))
+        .max_drift(1000)
+        .max_lag(5000)
+        .build()
+        .unwrap();
+
+    // Lagging timestamps within the bound are accepted, the clock is not affected.
+    let t1 = HlcTimestamp::from_parts(EPOCH + 5000, 7).unwrap();
+    assert_eq!(g.update(&t1).unwrap().parts(), (EPOCH + 10_000, 1));
+
+    // Stale timestamps are rejected, with the skew reported as negative.
+    let t2 = HlcTimestamp::from_parts(EPOCH + 4999, 7).unwrap();
+    let err = g.update(&t2).unwrap_err();
+    assert_eq!(
+        err,
+        HlcError::DriftTooLarge(EPOCH + 4999, EPOCH + 10_000, -5001, 5000)
+    );
+    assert_eq!(
+        err.to_string(),
+        format!(
+            "Drift exceeded the maximum allowed: incoming timestamp {}, wall-clock time {}, skew \
+             -5001 (max 5000)",
+            EPOCH + 4999,
+            EPOCH + 10_000
+        )
+    );
+    assert_eq!(g.drift_violations(), 1);
+
+    // Lag is not checked by default, regardless of how far the wall-clock time is
+    // ahead.
+    let g = HlcGenerator::builder()
+        .clock(ManualClock::new(EPOCH + (1 << 40)))
+        .max_drift(1000)
+        .build()
+        .unwrap();
+    assert_eq!(g.update(&t2).unwrap().parts(), (EPOCH + (1 << 40), 1));
+
+    // Reported, rather than rejected.
+    let g = HlcGenerator::builder()
+        .clock(ManualClock::new(EPOCH + 10_000))
+        .max_lag(5000)
+        .drift_policy(DriftPolicy::Report)
+        .build()
+        .unwrap();
+    assert_eq!(g.update(&t2).unwrap().parts(), (EPOCH + 10_000, 1));
+    assert_eq!(g.drift_violations(), 1);
+}
+
+#[test]
+fn drift_policies() {
+    type Layout = BitLayout<42, 12, 10>;
+    type Ts = HlcTimestampOf<Layout>;
+
+    let generator = |drift_policy| {
+        HlcGenerator::builder()
+            .clock(ManualClock::new(EPOCH + 10_000))
+            .layout::<Layout>()
+            .node_id(1)
+            .max_drift(1000)
+            .drift_policy(drift_policy)
+            .build()
+            .unwrap()
+    };
+    let far = Ts::from_parts_with_node(EPOCH + 20_000, 5, 2).unwrap();
+    let near = Ts::from_parts_with_node(EPOCH + 10_500, 5, 2).unwrap();
+
+    // Reject: state is intact.
+    let g = generator(DriftPolicy::Reject);
+    assert_eq!(
+        g.update(&far),
+        Err(HlcError::DriftTooLarge(
+            EPOCH + 20_000,
+            EPOCH + 10_000,
+            10_000,
+            1000
+        ))
+    );
+    assert_eq!(g.timestamp().parts(), (EPOCH + 10_000, 0));
+    assert_eq!(g.drift_violations(), 1);
+
+    // Clamp: physical time advances up to the maximum drift only.
+    let g = generator(DriftPolicy::Clamp);
+    let ts = g.update(&far).unwrap();
+    assert_eq!(ts.parts(), (EPOCH + 11_000, 0));
+    assert!(ts < far);
+    assert_eq!(g.update(&far).unwrap().parts(), (EPOCH + 11_000, 1));
+    assert_eq!(g.drift_violations(), 2);
+    assert_eq!(g.clamped(), 2);
+    // Timestamps within the drift are accepted as usual.
+    assert_eq!(g.update(&near).unwrap().parts(), (EPOCH + 11_000, 2));
+    assert_eq!(g.clamped(), 2);
+
+    // Report: accepted as is.
+    let g = generator(DriftPolicy::Report);
+    let ts = g.update(&far).unwrap();
+    assert_eq!(
+        (ts.timestamp(), ts.count(), ts.node()),
+        (EPOCH + 20_000, 6, 1)
+    );
+    assert_eq!(g.drift_violations(), 1);
+
+    // Quarantine: the peer is rejected until released, others are not affected.
+    let g = generator(DriftPolicy::Quarantine);
+    assert_eq!(
+        g.update(&far),
+        Err(HlcError::DriftTooLarge(
+            EPOCH + 20_000,
+            EPOCH + 10_000,
+            10_000,
+            1000
+        ))
+    );
+    assert_eq!(g.update(&near), Err(HlcError::PeerQuarantined(2)));
+    assert_eq!(g.quarantined_peers(), vec![2]);
+    let other = Ts::from_parts_with_node(EPOCH + 10_500, 5, 3).unwrap();
+    assert_eq!(g.update(&other).unwrap().parts(), (EPOCH + 10_500, 6));
+    assert!(g.release_peer(2));
+    assert!(!g.release_peer(2));
+    assert_eq!(g.update(&near).unwrap().parts(), (EPOCH + 10_500, 7));
+    assert_eq!(g.drift_violations(), 1);
+
+    // Without node identifiers, peers can only be told apart by `update_from()`.
+    let g = HlcGenerator::builder()
+        .clock(ManualClock::new(EPOCH + 10_000))
+        .max_drift(1000)
+        .drift_policy(DriftPolicy::Quarantine)
+        .build()
+        .unwrap();
+    let far = HlcTimestamp::from_parts(EPOCH + 12_000, 0).unwrap();
+    let near = HlcTimestamp::from_parts(EPOCH + 10_500, 0).unwrap();
+    assert!(matches!(g.update(&near), Err(HlcError::InvalidConfig(_))));
+    assert!(matches!(
+        g.update_from(1, &far),
+        Err(HlcError::DriftTooLarge(..))
+    ));
+    assert_eq!(g.update_from(1, &near), Err(HlcError::PeerQuarantined(1)));
+    assert_eq!(
+        g.update_from(2, &near).unwrap().parts(),
+        (EPOCH + 10_500, 1)
+    );
+    assert_eq!(g.quarantined_peers(), vec![1]);
+}
+
+#[test]
+fn peer_skew() {
+    let g = Arc::new(
+        HlcGenerator::builder()
+            .clock(ManualClock::new(EPOCH + 10_000))
+            .max_drift(1000)
+            .build()
+            .unwrap(),
+    );
+
+    // Skew is recorded, even if the timestamp is rejected.
+    let ahead = HlcTimestamp::from_parts(EPOCH + 12_000, 0).unwrap();
+    assert!(g.update_from(1, &ahead).is_err());
+    let behind = HlcTimestamp::from_parts(EPOCH + 9_500, 0).unwrap();
+    assert!(g.update_from(1, &behind).is_ok());
+    let skew = g.peer_skew(1).unwrap();
+    assert_eq!(
+        (skew.last, skew.min, skew.max, skew.samples),
+        (-500, -500, 2000, 2)
+    );
+    assert_eq!(g.peer_skew(2), None);
+
+    // Plain updates are not tracked.
+    g.update(&behind).unwrap();
+    assert_eq!(g.peer_skew(1).unwrap().samples, 2);
+
+    // Concurrent updates from several peers.
+    let handles = (2..6)
+        .map(|peer| {
+            let g = g.clone();
+            std::thread::spawn(move || {
+                for i in 0..100 {
+                    let ts = HlcTimestamp::from_parts(EPOCH + 10_000 + i, 0).unwrap();
+                    g.update_from(peer, &ts).unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    let skews = g.peer_skews();
+    assert_eq!(
+        skews.iter().map(|(peer, _)| *peer).collect::<Vec<_>>(),
+        vec![1, 2, 3, 4, 5]
+    );
+    assert!(
+        skews[1..]
+            .iter()
+            .all(|(_, skew)| skew.samples == 100 && skew.max == 99)
+    );
+
+    assert_eq!(g.forget_peer(1).map(|skew| skew.samples), Some(2));
+    assert_eq!(g.peer_skew(1), None);
+
+    // Quarantine is keyed by the peer identifier.
+    let g = HlcGenerator::builder()
+        .clock(ManualClock::new(EPOCH + 10_000))
+        .layout::<BitLayout<42, 12, 10>>()
+        .max_drift(1000)
+        .drift_policy(DriftPolicy::Quarantine)
+        .build()
+        .unwrap();
+    let ts = HlcTimestampOf::from_parts(EPOCH + 12_000, 0).unwrap();
+    assert!(g.update_from(42, &ts).is_err());
+    assert_eq!(g.quarantined_peers(), vec![42]);
+}
+
+#[test]
+fn node_id() {
+    type Layout = BitLayout<42, 12, 10>;
+
+    let node = |node_id| {
+        let g: HlcGenerator<ManualClock, Layout> = HlcGenerator::with_config(HlcConfig {
+            node_id,
+            ..Default::default()
+        })
+        .unwrap();
+        g.set_current_timestamp(EPOCH + 42);
+        g
+    };
+    let (g1, g2) = (node(1), node(2));
+    assert_eq!((g1.node_id(), g2.node_id()), (1, 2));
+
+    // Same wall-clock time and logical count, yet IDs do not collide.
+    let t1 = g1.next_timestamp().unwrap();
+    let t2 = g2.next_timestamp().unwrap();
+    assert_eq!(t1.parts(), t2.parts());
+    assert_eq!((t1.node(), t2.node()), (1, 2));
+    assert!(t1 < t2);
+
+    // Updated timestamps carry the local node identifier.
+    let t3 = g1.update(&t2).unwrap();
+    assert_eq!(t3.parts(), (EPOCH + 42, 1));
+    assert_eq!(t3.node(), 1);
+    assert!(t3 > t2);
+
+    // Node identifier must fit into the layout.
+    let res: Result<HlcGenerator<ManualClock, Layout>, _> = HlcGenerator::with_config(HlcConfig {
+        node_id: 1 << 10,
+        ..Default::default()
+    });
+    assert_eq!(
+        res.err(),
+        Some(hlc_gen::error::HlcError::NodeIdExceedsMax(
+            1 << 10,
+            HlcTimestampOf::<Layout>::NODE_MAX
+        ))
+    );
+}
+
+#[test]
+fn overflow_policy() {
+    // 2 bits of logical clock, i.e. at most 4 timestamps per millisecond.
+    type Layout = BitLayout<42, 2>;
+
+    let generator = |overflow_policy| {
+        let g: HlcGenerator<ManualClock, Layout> = HlcGenerator::with_config(HlcConfig {
+            overflow_policy,
+            ..Default::default()
+        })
+        .unwrap();
+        g.set_current_timestamp(EPOCH + 42);
+        g
+    };
+
+    // Error: no timestamps are generated, until the wall-clock ticks.
+    let g = generator(OverflowPolicy::Error);
+    let counts = (0..5)
+        .map(|_| g.next_timestamp().map(|t| t.count()))
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![Some(0), Some(1), Some(2), Some(3), None]);
+    assert_eq!(g.overflows(), 1);
+    g.set_current_timestamp(EPOCH + 43);
+    assert_eq!(g.next_timestamp().unwrap().parts(), (EPOCH + 43, 0));
+
+    // BorrowNext: physical time moves ahead of the wall-clock.
+    let g = generator(OverflowPolicy::BorrowNext);
+    let parts = (0..5)
+        .map(|_| g.next_timestamp().unwrap().parts())
+        .collect::<Vec<_>>();
+    assert_eq!(parts, vec![
+        (EPOCH + 42, 0),
+        (EPOCH + 42, 1),
+        (EPOCH + 42, 2),
+        (EPOCH + 42, 3),
+        (EPOCH + 43, 0)
+    ]);
+    assert_eq!(g.overflows(), 1);
+    // Wall-clock catches up.
+    g.set_current_timestamp(EPOCH + 44);
+    assert_eq!(g.next_timestamp().unwrap().parts(), (EPOCH + 44, 0));
+    assert_eq!(g.overflows(), 1);
+
+    // Wait: the caller is blocked until the wall-clock ticks.
+    let g = Arc::new(generator(OverflowPolicy::Wait));
+    for _ in 0..4 {
+        g.next_timestamp().unwrap();
+    }
+    let handle = {
+        let g = g.clone();
+        std::thread::spawn(move || g.next_timestamp().unwrap())
+    };
+    while g.overflows() == 0 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    // Waiting caller spins for a while, but the wait is counted once.
+    std::thread::sleep(Duration::from_millis(20));
+    assert!(!handle.is_finished());
+    g.set_current_timestamp(EPOCH + 43);
+    assert_eq!(handle.join().unwrap().parts(), (EPOCH + 43, 0));
+    assert_eq!(g.overflows(), 1);
+}
+
+#[test]
+fn try_next_timestamp() {
+    type Layout = BitLayout<42, 2>;
+    let g: HlcGenerator<ManualClock, Layout> = HlcGenerator::with_max_drift(0);
+    g.set_current_timestamp(EPOCH + 42);
+
+    // Too many timestamps within a millisecond.
+    for _ in 0..4 {
+        g.try_next_timestamp().unwrap();
+    }
+    assert_eq!(
+        g.try_next_timestamp(),
+        Err(HlcError::LogicalClockOverflow(EPOCH + 42, EPOCH + 42))
+    );
+    assert_eq!(g.next_timestamp(), None);
+
+    // Clock is set back, so the physical time cannot advance.
+    g.set_current_timestamp(EPOCH + 10);
+    assert_eq!(
+        g.try_next_timestamp(),
+        Err(HlcError::LogicalClockOverflow(EPOCH + 42, EPOCH + 10))
+    );
+
+    // Clock is set to 1970.
+    g.set_current_timestamp(0);
+    assert_eq!(
+        g.try_next_timestamp(),
+        Err(HlcError::TimestampBelowMin(0, EPOCH))
+    );
+
+    // Clock is back to normal.
+    g.set_current_timestamp(EPOCH + 43);
+    assert_eq!(g.try_next_timestamp().unwrap().parts(), (EPOCH + 43, 0));
+
+    // Wall-clock time beyond the range of the layout.
+    type Short = BitLayout<10, 2>;
+    let g: HlcGenerator<ManualClock, Short> = HlcGenerator::with_max_drift(0);
+    g.set_current_timestamp(EPOCH + (1 << 10));
+    assert_eq!(
+        g.try_next_timestamp(),
+        Err(HlcError::PhysicalTimeExceedsMax(
+            EPOCH + (1 << 10),
+            EPOCH + (1 << 10) - 1
+        ))
+    );
+}
+
+#[test]
+fn next_timestamps() {
+    // 2 bits of logical clock, i.e. at most 4 timestamps per millisecond.
+    type Layout = BitLayout<42, 2>;
+    let generator = |overflow_policy| {
+        let g: HlcGenerator<ManualClock, Layout> = HlcGenerator::with_config(HlcConfig {
+            max_drift: 10,
+            overflow_policy,
+            ..Default::default()
+        })
+        .unwrap();
+        g.set_current_timestamp(EPOCH + 42);
+        g
+    };
+
+    let g = generator(OverflowPolicy::BorrowNext);
+    let range = g.next_timestamps(3).unwrap();
+    assert_eq!(range.len(), 3);
+    assert_eq!(range.map(|t| t.parts()).collect::<Vec<_>>(), vec![
+        (EPOCH + 42, 0),
+        (EPOCH + 42, 1),
+        (EPOCH + 42, 2)
+    ]);
+    assert_eq!(g.overflows(), 0);
+
+    // Spill-over across the logical clock boundary.
+    let range = g.next_timestamps(6).unwrap();
+    assert_eq!(range.map(|t| t.parts()).collect::<Vec<_>>(), vec![
+        (EPOCH + 42, 3),
+        (EPOCH + 43, 0),
+        (EPOCH + 43, 1),
+        (EPOCH + 43, 2),
+        (EPOCH + 43, 3),
+        (EPOCH + 44, 0)
+    ]);
+    assert_eq!(g.overflows(), 1);
+    assert_eq!(g.timestamp().parts(), (EPOCH + 44, 0));
+
+    // Starting in a borrowed millisecond.
+    g.set_current_timestamp(EPOCH + 44);
+    for _ in 0..3 {
+        g.next_timestamp().unwrap();
+    }
+    let range = g.next_timestamps(1).unwrap();
+    assert_eq!(range.start().unwrap().parts(), (EPOCH + 45, 0));
+    assert_eq!(g.overflows(), 2);
+
+    // Empty range does not change the state.
+    let range = g.next_timestamps(0).unwrap();
+    assert_eq!(range.len(), 0);
+    assert_eq!(g.timestamp().parts(), (EPOCH + 45, 0));
+
+    // Range running ahead of the wall-clock by more than the maximum drift is
+    // rejected, leaving the state intact.
+    assert_eq!(
+        g.next_timestamps(40).err(),
+        Some(HlcError::LogicalClockOverflow(EPOCH + 55, EPOCH + 44))
+    );
+    assert!(g.next_timestamps(u64::MAX).is_err());
+    assert_eq!(g.timestamp().parts(), (EPOCH + 45, 0));
+    assert_eq!(g.overflows(), 4);
+    assert_eq!(g.next_timestamp().unwrap().parts(), (EPOCH + 45, 1));
+
+    // Error: the range must fit into a single millisecond.
+    let g = generator(OverflowPolicy::Error);
+    g.next_timestamps(3).unwrap();
+    assert_eq!(
+        g.next_timestamps(2).err(),
+        Some(HlcError::LogicalClockExceedsMax(4, 3))
+    );
+    assert_eq!(
+        g.next_timestamps(u64::MAX).err(),
+        Some(HlcError::LogicalClockExceedsMax(u64::MAX, 3))
+    );
+    assert_eq!(g.timestamp().parts(), (EPOCH + 42, 2));
+    assert_eq!(g.overflows(), 2);
+    let range = g.next_timestamps(1).unwrap();
+    assert_eq!(range.start().unwrap().parts(), (EPOCH + 42, 3));
+
+    // Wait: the range larger than the logical clock fails right away.
+    let g = generator(OverflowPolicy::Wait);
+    let state = g.timestamp();
+    assert_eq!(
+        g.next_timestamps(5).err(),
+        Some(HlcError::LogicalClockExceedsMax(4, 3))
+    );
+    assert_eq!(g.timestamp(), state);
+
+    // Batches and single timestamps interleave without duplicates.
+    let g = Arc::new(HlcGenerator::default());
+    let handles = (0..10)
+        .map(|i| {
+            let g = g.clone();
+            std::thread::spawn(move || {
+                let mut timestamps = vec![];
+                for _ in 0..100 {
+                    if i % 2 == 0 {
+                        timestamps.extend(g.next_timestamps(100).unwrap());
+                    } else {
+                        timestamps.push(g.next_timestamp().unwrap());
+                    }
+                }
+                timestamps
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut timestamps = vec![];
+    for handle in handles {
+        timestamps.extend(handle.join().unwrap());
+    }
+    let len = timestamps.len();
+    assert_eq!(len, 5 * 100 * 100 + 5 * 100);
+    timestamps.sort();
+    timestamps.dedup();
+    assert_eq!(timestamps.len(), len);
+}
+
+#[test]
+fn custom_clock() {
+    // Clock which needs configuration, so cannot implement `Default`.
+    struct OffsetClock {
+        base: Arc<ManualClock>,
+        offset: i64,
+    }
+
+    impl ClockSource for OffsetClock {
+        fn current_timestamp(&self) -> i64 {
+            self.base.current_timestamp() + self.offset
+        }
+    }
+
+    let base = Arc::new(ManualClock::new(EPOCH + 1000));
+    let shared: HlcGenerator<_> =
+        HlcGenerator::with_clock(base.clone(), HlcConfig::default()).unwrap();
+    let offset: HlcGenerator<_> = HlcGenerator::with_clock(
+        OffsetClock {
+            base: base.clone(),
+            offset: 500,
+        },
+        HlcConfig::default(),
+    )
+    .unwrap();
+    assert_eq!(shared.timestamp().parts(), (EPOCH + 1000, 0));
+    assert_eq!(offset.timestamp().parts(), (EPOCH + 1500, 0));
+
+    base.set_current_timestamp(EPOCH + 2000);
+    assert_eq!(shared.next_timestamp().unwrap().parts(), (EPOCH + 2000, 0));
+    assert_eq!(offset.next_timestamp().unwrap().parts(), (EPOCH + 2500, 0));
+
+    // Configuration is still validated.
+    let res = HlcGenerator::<_, BitLayout<42, 12, 10>>::with_clock(base, HlcConfig {
+        node_id: 1 << 10,
+        ..Default::default()
+    });
+    assert!(matches!(res, Err(HlcError::NodeIdExceedsMax(1024, 1023))));
+}
+
+#[test]
+fn builder() {
+    let g = HlcGenerator::builder()
+        .clock(ManualClock::new(EPOCH + 1000))
+        .layout::<BitLayout<42, 12, 10>>()
+        .node_id(7)
+        .overflow_policy(OverflowPolicy::BorrowNext)
+        .build()
+        .unwrap();
+    assert_eq!(g.node_id(), 7);
+    assert_eq!(g.timestamp().parts(), (EPOCH + 1000, 0));
+
+    // Exhaust the logical clock to check the overflow policy.
+    for _ in 0..=HlcTimestampOf::<BitLayout<42, 12, 10>>::LC_MAX {
+        g.next_timestamp().unwrap();
+    }
+    assert_eq!(g.overflows(), 1);
+
+    // Initial state is used, if it is ahead of the wall-clock time.
+    let g = HlcGeneratorBuilder::new()
+        .clock(ManualClock::new(EPOCH + 1000))
+        .initial_state(EPOCH + 1500, 10)
+        .max_drift(1000)
+        .build()
+        .unwrap();
+    assert_eq!(g.next_timestamp().unwrap().parts(), (EPOCH + 1500, 11));
+    let g = HlcGeneratorBuilder::new()
+        .clock(ManualClock::new(EPOCH + 1000))
+        .initial_state(EPOCH + 500, 10)
+        .build()
+        .unwrap();
+    assert_eq!(g.next_timestamp().unwrap().parts(), (EPOCH + 1000, 1));
+
+    // Invalid combinations.
+    let res = HlcGenerator::builder().node_id(1).build();
+    assert!(matches!(res, Err(HlcError::NodeIdExceedsMax(1, 0))));
+    let res = HlcGenerator::builder()
+        .clock(ManualClock::new(EPOCH + 1000))
+        .initial_state(EPOCH + 5000, 0)
+        .max_drift(1000)
+        .build();
+    assert_eq!(
+        res.err(),
+        Some(HlcError::DriftTooLarge(
+            EPOCH + 5000,
+            EPOCH + 1000,
+            4000,
+            1000
+        ))
+    );
+    let res = HlcGenerator::builder()
+        .initial_state(EPOCH, HlcTimestamp::LC_MAX + 1)
+        .build();
+    assert!(matches!(res, Err(HlcError::LogicalClockExceedsMax(..))));
+    let dir = tempfile::tempdir().unwrap();
+    let res = HlcGenerator::builder()
+        .persistence(FilePersistence::new(dir.path().join("hlc")), 0)
+        .build();
+    assert!(matches!(res, Err(HlcError::InvalidConfig(_))));
+}
+
+#[test]
+fn persistence() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("hlc");
+    let config = HlcConfig::default();
+
+    let g =
+        HlcGenerator::<ManualClock>::with_persistence(config, FilePersistence::new(&path), 1000)
+            .unwrap();
+    assert_eq!(g.high_water_mark(), None);
+    g.set_current_timestamp(EPOCH + 10_000);
+    let ts = g.next_timestamp().unwrap();
+    assert_eq!(ts.parts(), (EPOCH + 10_000, 0));
+    assert_eq!(g.high_water_mark(), Some(EPOCH + 11_000));
+
+    // Below the high-water mark, nothing is persisted.
+    g.set_current_timestamp(EPOCH + 10_999);
+    g.next_timestamp().unwrap();
+    assert_eq!(g.high_water_mark(), Some(EPOCH + 11_000));
+
+    // Both batches and updates move the high-water mark.
+    g.set_current_timestamp(EPOCH + 11_000);
+    g.next_timestamps(10).unwrap();
+    assert_eq!(g.high_water_mark(), Some(EPOCH + 12_000));
+    let incoming = HlcTimestamp::from_parts(EPOCH + 12_500, 0).unwrap();
+    let last = g.update(&incoming).unwrap();
+    assert_eq!(g.high_water_mark(), Some(EPOCH + 13_500));
+    drop(g);
+
+    // Restart with the wall-clock stepped back: the generator resumes from the
+    // persisted high-water mark.
+    let g =
+        HlcGenerator::<ManualClock>::with_persistence(config, FilePersistence::new(&path), 1000)
+            .unwrap();
+    assert_eq!(g.high_water_mark(), Some(EPOCH + 13_500));
+    g.set_current_timestamp(EPOCH + 5_000);
+    let ts = g.next_timestamp().unwrap();
+    assert!(ts > last);
+    assert_eq!(ts.parts(), (EPOCH + 13_500, 1));
+    assert_eq!(g.high_water_mark(), Some(EPOCH + 14_500));
+}
+
+#[test]
+fn persistence_failure() {
+    struct ReadOnly;
+
+    impl Persistence for ReadOnly {
+        fn load(&self) -> HlcResult<Option<i64>> {
+            Ok(Some(EPOCH + 1000))
+        }
+
+        fn store(&self, _: i64) -> HlcResult<()> {
+            Err(HlcError::Persistence("read-only".to_string()))
+        }
+    }
+
+    let g = HlcGenerator::<ManualClock>::with_persistence(HlcConfig::default(), ReadOnly, 1000)
+        .unwrap();
+    assert_eq!(g.timestamp().parts(), (EPOCH + 1000, 0));
+    assert_eq!(
+        g.try_next_timestamp(),
+        Err(HlcError::Persistence("read-only".to_string()))
+    );
+    assert_eq!(g.high_water_mark(), Some(EPOCH + 1000));
+}
+
 #[test]
 fn multi_step() {
     let max_drift = 1000;
@@ -0,0 +1,85 @@
+// Run with `RUSTFLAGS="--cfg loom" cargo test --release --test loom`.
+#![cfg(loom)]
+
+mod common;
+
+use {
+    common::EPOCH,
+    hlc_gen::{HlcGenerator, HlcTimestamp, source::ManualClock},
+    loom::{sync::Arc, thread},
+};
+
+/// Generator with a clock standing still, so that every timestamp is produced
+/// by the logical clock, and relies on the CAS loop for uniqueness.
+fn generator() -> Arc<HlcGenerator<ManualClock>> {
+    Arc::new(
+        HlcGenerator::builder()
+            .clock(ManualClock::new(EPOCH + 1000))
+            .build()
+            .unwrap(),
+    )
+}
+
+#[test]
+fn concurrent_next_timestamp() {
+    loom::model(|| {
+        let g = generator();
+
+        let handles = (0..2)
+            .map(|_| {
+                let g = g.clone();
+                thread::spawn(move || {
+                    let t1 = g.next_timestamp().unwrap();
+                    let t2 = g.next_timestamp().unwrap();
+                    assert!(t1 < t2);
+                    [t1, t2]
+                })
+            })
+            .collect::<Vec<_>>();
+        let mut timestamps = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect::<Vec<_>>();
+
+        // All the timestamps are unique, and the state is past all of them.
+        timestamps.sort();
+        timestamps.dedup();
+        assert_eq!(timestamps.len(), 4);
+        assert_eq!(g.timestamp(), timestamps[3]);
+    });
+}
+
+#[test]
+fn concurrent_next_timestamp_and_update() {
+    loom::model(|| {
+        let g = generator();
+        let incoming = HlcTimestamp::from_parts(EPOCH + 2000, 5).unwrap();
+
+        let local = {
+            let g = g.clone();
+            thread::spawn(move || {
+                let t1 = g.next_timestamp().unwrap();
+                let t2 = g.next_timestamp().unwrap();
+                assert!(t1 < t2);
+                [t1, t2]
+            })
+        };
+        let remote = {
+            let g = g.clone();
+            thread::spawn(move || {
+                let ts = g.update(&incoming).unwrap();
+                assert!(ts > incoming);
+                ts
+            })
+        };
+
+        let [t1, t2] = local.join().unwrap();
+        let received = remote.join().unwrap();
+        assert!(received != t1 && received != t2);
+
+        // Whatever the interleaving, the final state is past all timestamps.
+        let last = g.timestamp();
+        assert!(last >= t2 && last >= received);
+        assert!(g.next_timestamp().unwrap() > last);
+    });
+}
@@ -0,0 +1,175 @@
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use {
+    common::{EPOCH, SteadyClock},
+    hlc_gen::{HlcGenerator, HlcTimestamp, source::ManualClock, telemetry},
+    metrics_util::debugging::{DebugValue, DebuggingRecorder},
+    std::sync::{Arc, atomic::AtomicI64},
+};
+
+/// Recorded metric: name, labels and value.
+type Metric = (String, Vec<(String, String)>, DebugValue);
+
+/// Runs `f` with a local recorder, returning the recorded metrics.
+fn capture(f: impl FnOnce()) -> Vec<Metric> {
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    metrics::with_local_recorder(&recorder, f);
+    snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let key = key.key();
+            let labels = key
+                .labels()
+                .map(|label| (label.key().to_owned(), label.value().to_owned()))
+                .collect();
+            (key.name().to_owned(), labels, value)
+        })
+        .collect()
+}
+
+/// Value of the metric with the given name and labels.
+fn value<'a>(metrics: &'a [Metric], name: &str, labels: &[(&str, &str)]) -> Option<&'a DebugValue> {
+    metrics
+        .iter()
+        .find(|(n, l, _)| {
+            n == name
+                && l.len() == labels.len()
+                && labels
+                    .iter()
+                    .all(|(k, v)| l.iter().any(|(lk, lv)| lk == k && lv == v))
+        })
+        .map(|(_, _, value)| value)
+}
+
+#[test]
+fn generator_metrics() {
+    let metrics = capture(|| {
+        let g = HlcGenerator::builder()
+            .clock(ManualClock::new(EPOCH + 1000))
+            .max_drift(100)
+            .max_lag(100)
+            .build()
+            .unwrap();
+
+        // Three timestamps within the same millisecond, then the next one.
+        g.next_timestamp().unwrap();
+        g.next_timestamps(2).unwrap();
+        g.set_current_timestamp(EPOCH + 1001);
+        g.next_timestamp().unwrap();
+
+        // Receive events, moving physical time ahead of the wall-clock time.
+        g.update(&HlcTimestamp::from_parts(EPOCH + 1050, 0).unwrap())
+            .unwrap();
+        assert!(
+            g.update(&HlcTimestamp::from_parts(EPOCH + 2000, 0).unwrap())
+                .is_err()
+        );
+        assert!(
+            g.update(&HlcTimestamp::from_parts(EPOCH, 0).unwrap())
+                .is_err()
+        );
+
+        // Wall-clock goes backwards.
+        g.set_current_timestamp(EPOCH + 990);
+        g.next_timestamp().unwrap();
+    });
+
+    assert_eq!(
+        value(&metrics, telemetry::TIMESTAMPS_ISSUED, &[(
+            "event", "local"
+        )]),
+        Some(&DebugValue::Counter(5))
+    );
+    assert_eq!(
+        value(&metrics, telemetry::TIMESTAMPS_ISSUED, &[(
+            "event", "receive"
+        )]),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        value(&metrics, telemetry::DRIFT_VIOLATIONS, &[
+            ("direction", "ahead"),
+            ("policy", "reject")
+        ]),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        value(&metrics, telemetry::DRIFT_VIOLATIONS, &[
+            ("direction", "behind"),
+            ("policy", "reject")
+        ]),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        value(&metrics, telemetry::CLOCK_REGRESSIONS, &[]),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        value(&metrics, telemetry::CLOCK_REGRESSION_MS, &[]),
+        Some(&DebugValue::Histogram(vec![11.0.into()]))
+    );
+
+    // Millisecond `EPOCH + 1000` ended with the count of 3 (the initial state took
+    // 0), `EPOCH + 1001` with 0.
+    assert_eq!(
+        value(&metrics, telemetry::LOGICAL_CLOCK_HIGH_WATER, &[]),
+        Some(&DebugValue::Histogram(vec![3.0.into(), 0.0.into()]))
+    );
+
+    // Last timestamp is at `EPOCH + 1050`, while the wall-clock is at `EPOCH +
+    // 990`.
+    assert_eq!(
+        value(&metrics, telemetry::PHYSICAL_TIME_AHEAD_MS, &[]),
+        Some(&DebugValue::Gauge(60.0.into()))
+    );
+
+    // Single thread never retries.
+    assert_eq!(value(&metrics, telemetry::CAS_RETRIES, &[]), None);
+}
+
+#[test]
+fn no_false_regressions() {
+    // Local recorders are per thread, so the global one is used (installed once per
+    // test binary, and only this test records into it).
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    recorder.install().unwrap();
+
+    let g = Arc::new(
+        HlcGenerator::builder()
+            .clock(SteadyClock(AtomicI64::new(EPOCH)))
+            .build()
+            .unwrap(),
+    );
+    let handles = (0..8)
+        .map(|_| {
+            let g = g.clone();
+            std::thread::spawn(move || {
+                for _ in 0..20_000 {
+                    g.next_timestamp().unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    let metrics = snapshotter.snapshot().into_vec();
+    let issued = metrics
+        .iter()
+        .find(|(key, ..)| key.key().name() == telemetry::TIMESTAMPS_ISSUED)
+        .map(|(.., value)| value);
+    assert_eq!(issued, Some(&DebugValue::Counter(160_000)));
+    // Readings delayed by the scheduling of the threads are not regressions.
+    assert!(
+        !metrics
+            .iter()
+            .any(|(key, ..)| key.key().name() == telemetry::CLOCK_REGRESSIONS)
+    );
+}
@@ -0,0 +1,114 @@
+#![cfg(feature = "proptest")]
+
+mod common;
+
+use {
+    common::EPOCH,
+    hlc_gen::{
+        HlcGenerator,
+        HlcTimestamp,
+        HlcTimestampOf,
+        layout::BitLayout,
+        source::{ClockSource, ManualClock},
+        strategy,
+    },
+    proptest::prelude::*,
+    std::sync::Arc,
+};
+
+/// Wall-clock time the generators start at.
+const START: i64 = EPOCH + 365 * 24 * 60 * 60 * 1000;
+
+/// Operation applied to the generator.
+#[derive(Debug, Clone)]
+enum Op {
+    /// Move the wall-clock (possibly backwards), and generate a timestamp.
+    Next(i64),
+    /// Move the wall-clock, and receive a timestamp, which is the given number
+    /// of milliseconds away from the wall-clock.
+    Update(i64, i64, u64),
+}
+
+fn ops() -> impl Strategy<Value = Vec<Op>> {
+    prop::collection::vec(
+        prop_oneof![
+            (-100i64..=100).prop_map(Op::Next),
+            (-100i64..=100, -1000i64..=1000, 0u64..=1000)
+                .prop_map(|(step, offset, count)| Op::Update(step, offset, count)),
+        ],
+        1..200,
+    )
+}
+
+proptest! {
+    #[test]
+    fn u64_round_trip(ts: HlcTimestamp) {
+        prop_assert_eq!(HlcTimestamp::try_from(ts.as_u64()), Ok(ts));
+    }
+
+    #[test]
+    fn u64_round_trip_with_node(ts: HlcTimestampOf<BitLayout<42, 12, 10>>) {
+        prop_assert_eq!(HlcTimestampOf::try_from(ts.as_u64()), Ok(ts));
+    }
+
+    #[test]
+    fn add_sub_inverse(
+        ts in strategy::timestamps(START..=START + 1_000_000, 0..=1000),
+        d in 0u64..=1_000_000,
+    ) {
+        let ts: HlcTimestamp = ts;
+        let moved = ts + d;
+        prop_assert_eq!(moved - d, ts);
+        prop_assert_eq!(moved - ts, d as i64);
+        prop_assert_eq!(ts - moved, -(d as i64));
+        prop_assert_eq!(moved.count(), ts.count());
+
+        let mut t = ts;
+        t += d;
+        prop_assert_eq!(t, moved);
+        t -= d;
+        prop_assert_eq!(t, ts);
+    }
+
+    #[test]
+    fn next_timestamp_monotonic(steps in prop::collection::vec(-100i64..=100, 1..500)) {
+        let clock = Arc::new(ManualClock::new(START));
+        let g = HlcGenerator::builder().clock(clock.clone()).build().unwrap();
+
+        let mut prev = g.timestamp();
+        for step in steps {
+            clock.advance(step);
+            let ts = g.next_timestamp().unwrap();
+            prop_assert!(ts > prev);
+            prop_assert!(ts.timestamp() >= clock.current_timestamp());
+            prev = ts;
+        }
+    }
+
+    #[test]
+    fn update_monotonic(ops in ops()) {
+        let clock = Arc::new(ManualClock::new(START));
+        let g = HlcGenerator::builder().clock(clock.clone()).build().unwrap();
+
+        let mut prev = g.timestamp();
+        for op in ops {
+            let ts = match op {
+                Op::Next(step) => {
+                    clock.advance(step);
+                    g.next_timestamp().unwrap()
+                }
+                Op::Update(step, offset, count) => {
+                    let now = clock.advance(step);
+                    let incoming = HlcTimestamp::from_parts(now + offset, count).unwrap();
+                    let ts = g.update(&incoming).unwrap();
+                    // Receive event happens after the send event.
+                    prop_assert!(ts > incoming);
+                    ts
+                }
+            };
+            prop_assert!(ts > prev);
+            prop_assert_eq!(g.timestamp(), ts);
+            prev = ts;
+        }
+    }
+}
@@ -0,0 +1,98 @@
+#![cfg(feature = "serde")]
+
+mod common;
+
+use {
+    common::EPOCH,
+    hlc_gen::{
+        HlcGenerator,
+        HlcTimestamp,
+        HlcTimestampOf,
+        config::{DriftPolicy, HlcConfig, OverflowPolicy},
+        layout::BitLayout,
+    },
+};
+
+#[test]
+fn human_readable() {
+    let timestamp = HlcTimestamp::from_parts(EPOCH + 12345, 67890).unwrap();
+
+    let json = serde_json::to_string(&timestamp).unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"timestamp":{},"count":67890}}"#, EPOCH + 12345)
+    );
+    assert_eq!(
+        serde_json::from_str::<HlcTimestamp>(&json).unwrap(),
+        timestamp
+    );
+
+    // Node identifier is included, if the layout reserves bits for it.
+    type Ts = HlcTimestampOf<BitLayout<42, 12, 10>>;
+    let timestamp = Ts::from_parts_with_node(EPOCH + 12345, 678, 9).unwrap();
+    let json = serde_json::to_string(&timestamp).unwrap();
+    assert_eq!(
+        json,
+        format!(r#"{{"timestamp":{},"count":678,"node":9}}"#, EPOCH + 12345)
+    );
+    assert_eq!(serde_json::from_str::<Ts>(&json).unwrap(), timestamp);
+}
+
+#[test]
+fn human_readable_rejects_malformed() {
+    // Timestamp below the epoch.
+    let json = format!(r#"{{"timestamp":{},"count":1}}"#, EPOCH - 1);
+    assert!(serde_json::from_str::<HlcTimestamp>(&json).is_err());
+
+    // Logical clock exceeding its maximum.
+    let json = format!(r#"{{"timestamp":{},"count":{}}}"#, EPOCH, 1 << 22);
+    assert!(serde_json::from_str::<HlcTimestamp>(&json).is_err());
+
+    // Node identifier not fitting into the layout.
+    let json = format!(r#"{{"timestamp":{},"count":1,"node":1}}"#, EPOCH);
+    assert!(serde_json::from_str::<HlcTimestamp>(&json).is_err());
+
+    // Unknown fields.
+    let json = format!(r#"{{"timestamp":{},"count":1,"foo":1}}"#, EPOCH);
+    assert!(serde_json::from_str::<HlcTimestamp>(&json).is_err());
+}
+
+#[test]
+fn binary() {
+    let timestamp = HlcTimestamp::from_parts(EPOCH + 12345, 67890).unwrap();
+
+    let bytes = bincode::serialize(&timestamp).unwrap();
+    assert_eq!(bytes, timestamp.as_u64().to_le_bytes());
+    assert_eq!(
+        bincode::deserialize::<HlcTimestamp>(&bytes).unwrap(),
+        timestamp
+    );
+
+    // Values with bits outside of the layout are rejected.
+    type Ts = HlcTimestampOf<BitLayout<40, 20>>;
+    let bytes = bincode::serialize(&u64::MAX).unwrap();
+    assert!(bincode::deserialize::<Ts>(&bytes).is_err());
+}
+
+#[test]
+fn config() {
+    let config: HlcConfig = serde_json::from_str(
+        r#"{"max_drift":1000,"drift_policy":"clamp","overflow_policy":"borrow_next"}"#,
+    )
+    .unwrap();
+    assert_eq!(config, HlcConfig {
+        max_drift: 1000,
+        max_lag: 0,
+        drift_policy: DriftPolicy::Clamp,
+        node_id: 0,
+        overflow_policy: OverflowPolicy::BorrowNext,
+    });
+    assert_eq!(
+        serde_json::from_str::<HlcConfig>("{}").unwrap(),
+        HlcConfig::default()
+    );
+    assert!(serde_json::from_str::<HlcConfig>(r#"{"max_drfit":1000}"#).is_err());
+
+    let g = HlcGenerator::builder().config(config).build().unwrap();
+    assert!(g.next_timestamp().is_some());
+}
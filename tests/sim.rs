@@ -0,0 +1,86 @@
+#![cfg(feature = "sim")]
+
+use hlc_gen::sim::{SimConfig, Simulation};
+
+#[test]
+fn invariants_hold() {
+    for seed in 0..10 {
+        let report = Simulation::new(SimConfig {
+            seed,
+            ..Default::default()
+        })
+        .run();
+        assert!(report.is_ok(), "seed {seed}: {:?}", report.violations);
+        assert!(report.delivered > 0 && report.lost > 0 && report.reordered > 0);
+        assert!(report.max_divergence <= 100);
+    }
+}
+
+#[test]
+fn invariants_hold_under_stress() {
+    // Many nodes, large skew, chatty nodes with long delays.
+    let report = Simulation::new(SimConfig {
+        nodes: 20,
+        seed: 7,
+        duration: 2000,
+        max_skew: 500,
+        send_probability: 0.9,
+        local_probability: 0.9,
+        max_delay: 1000,
+        loss_probability: 0.2,
+        ..Default::default()
+    })
+    .run();
+    assert!(report.is_ok(), "{:?}", report.violations);
+    assert!(report.max_count > 0);
+}
+
+#[test]
+fn deterministic() {
+    let config = SimConfig {
+        seed: 42,
+        duration: 1000,
+        ..Default::default()
+    };
+    let report = Simulation::new(config.clone()).run();
+    assert_eq!(report, Simulation::new(config.clone()).run());
+    assert_ne!(
+        report,
+        Simulation::new(SimConfig { seed: 43, ..config }).run()
+    );
+}
+
+#[test]
+fn fifo_channels() {
+    let report = Simulation::new(SimConfig {
+        seed: 1,
+        loss_probability: 0.0,
+        reorder: false,
+        ..Default::default()
+    })
+    .run();
+    assert!(report.is_ok(), "{:?}", report.violations);
+    assert_eq!((report.lost, report.reordered), (0, 0));
+}
+
+#[test]
+fn no_skew() {
+    // With perfectly synchronized clocks, physical time never diverges.
+    let report = Simulation::new(SimConfig {
+        seed: 3,
+        max_skew: 0,
+        ..Default::default()
+    })
+    .run();
+    assert!(report.is_ok(), "{:?}", report.violations);
+    assert_eq!(report.max_divergence, 0);
+}
+
+#[test]
+#[should_panic(expected = "loss probability must be within 0.0..=1.0, got 1.5")]
+fn invalid_probability() {
+    Simulation::new(SimConfig {
+        loss_probability: 1.5,
+        ..Default::default()
+    });
+}
@@ -1,6 +1,11 @@
 mod common;
 
-use {chrono::Utc, common::EPOCH, hlc_gen::HlcTimestamp, std::time::Duration};
+use {
+    chrono::Utc,
+    common::EPOCH,
+    hlc_gen::{HlcTimestamp, HlcTimestampOf, error::HlcError, layout::BitLayout},
+    std::time::Duration,
+};
 
 #[test]
 fn create_timestamp() {
@@ -33,3 +38,85 @@ fn from_and_to_u64() {
 
     assert_eq!(timestamp, back_to_timestamp);
 }
+
+#[test]
+fn string_round_trip() {
+    let timestamp = HlcTimestamp::from_parts(EPOCH + 12345, 67890).unwrap();
+
+    // Canonical form.
+    let canonical = timestamp.encode();
+    assert_eq!(format!("{timestamp:#}"), canonical);
+    assert_eq!(canonical.len(), 13);
+    assert_eq!(canonical.parse::<HlcTimestamp>(), Ok(timestamp));
+    assert_eq!(
+        canonical.to_lowercase().parse::<HlcTimestamp>(),
+        Ok(timestamp)
+    );
+
+    // Human-readable form.
+    let human = timestamp.to_string();
+    assert_eq!(human, "2024-01-01T00:00:12.345Z#67890");
+    assert_eq!(human.parse::<HlcTimestamp>(), Ok(timestamp));
+
+    // Other offsets are accepted as well.
+    assert_eq!(
+        "2024-01-01T02:00:12.345+02:00#67890".parse::<HlcTimestamp>(),
+        Ok(timestamp)
+    );
+
+    // Node identifier is included, if the layout reserves bits for it.
+    type Ts = HlcTimestampOf<BitLayout<42, 12, 10>>;
+    let timestamp = Ts::from_parts_with_node(EPOCH + 12345, 678, 9).unwrap();
+    let human = timestamp.to_string();
+    assert_eq!(human, "2024-01-01T00:00:12.345Z#678@9");
+    assert_eq!(human.parse::<Ts>(), Ok(timestamp));
+    assert_eq!(timestamp.encode().parse::<Ts>(), Ok(timestamp));
+
+    // Physical time beyond the range of the date-time is written as is.
+    type Wide = HlcTimestampOf<BitLayout<62, 1>>;
+    let timestamp = Wide::try_from((1 << 63) - 1).unwrap();
+    let human = timestamp.to_string();
+    assert_eq!(human, format!("{}#1", EPOCH + (1 << 62) - 1));
+    assert_eq!(human.parse::<Wide>(), Ok(timestamp));
+}
+
+#[test]
+fn string_sortable() {
+    let t1 = HlcTimestamp::from_parts(EPOCH + 12345, 67890).unwrap();
+    let t2 = HlcTimestamp::from_parts(EPOCH + 12345, 67891).unwrap();
+    let t3 = HlcTimestamp::from_parts(EPOCH + 12346, 0).unwrap();
+    assert!(t1.encode() < t2.encode());
+    assert!(t2.encode() < t3.encode());
+}
+
+#[test]
+fn string_parse_errors() {
+    assert_eq!(
+        "0123".parse::<HlcTimestamp>(),
+        Err(HlcError::InvalidEncodingLength(4, 13))
+    );
+    assert_eq!(
+        "0000000000U00".parse::<HlcTimestamp>(),
+        Err(HlcError::InvalidEncodingChar('U', 10))
+    );
+    assert!(matches!(
+        "2024-01-01 00:00:12#1".parse::<HlcTimestamp>(),
+        Err(HlcError::InvalidDateTime(_))
+    ));
+    assert!(matches!(
+        "2024-01-01T00:00:12.345Z#x".parse::<HlcTimestamp>(),
+        Err(HlcError::InvalidNumber(_))
+    ));
+    assert!(matches!(
+        "2024-01-01T00:00:12.345678Z#1".parse::<HlcTimestamp>(),
+        Err(HlcError::InvalidFormat(_))
+    ));
+    assert_eq!(
+        "2023-12-31T23:59:59.999Z#1".parse::<HlcTimestamp>(),
+        Err(HlcError::TimestampBelowMin(EPOCH - 1, EPOCH))
+    );
+    assert_eq!(
+        "2024-01-01T00:00:12.345Z#1@1".parse::<HlcTimestamp>(),
+        Err(HlcError::NodeIdExceedsMax(1, 0))
+    );
+}
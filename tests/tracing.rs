@@ -0,0 +1,209 @@
+#![cfg(feature = "tracing")]
+
+mod common;
+
+use {
+    common::{EPOCH, SteadyClock},
+    hlc_gen::{HlcGenerator, HlcTimestamp, HlcTimestampOf, layout::BitLayout, source::ManualClock},
+    parking_lot::Mutex,
+    std::{
+        collections::BTreeMap,
+        fmt::Debug,
+        sync::{Arc, atomic::AtomicI64},
+    },
+    tracing::{
+        Event,
+        Level,
+        Subscriber,
+        field::{Field, Visit},
+    },
+    tracing_subscriber::{Layer, layer::Context, prelude::*},
+};
+
+/// Captured event: level and fields (including the message).
+type Captured = (Level, BTreeMap<String, String>);
+
+/// Layer capturing all the events.
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<Captured>>>);
+
+impl<S: Subscriber> Layer<S> for Capture {
+    fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+        struct Fields(BTreeMap<String, String>);
+
+        impl Visit for Fields {
+            fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+                self.0.insert(field.name().to_owned(), format!("{value:?}"));
+            }
+
+            fn record_str(&mut self, field: &Field, value: &str) {
+                self.0.insert(field.name().to_owned(), value.to_owned());
+            }
+        }
+
+        let mut fields = Fields(BTreeMap::new());
+        event.record(&mut fields);
+        self.0.lock().push((*event.metadata().level(), fields.0));
+    }
+}
+
+/// Runs `f` with the capturing subscriber, returning the captured events.
+fn capture(f: impl FnOnce()) -> Vec<Captured> {
+    let capture = Capture::default();
+    tracing::subscriber::with_default(tracing_subscriber::registry().with(capture.clone()), f);
+    Arc::try_unwrap(capture.0).unwrap().into_inner()
+}
+
+/// Asserts that the event has the level, message and fields.
+fn assert_event(event: &Captured, level: Level, message: &str, fields: &[(&str, i64)]) {
+    assert_eq!(event.0, level);
+    assert_eq!(event.1["message"], message);
+    for (name, value) in fields {
+        assert_eq!(event.1[*name], value.to_string(), "field {name}");
+    }
+}
+
+#[test]
+fn drift_exceeded() {
+    let events = capture(|| {
+        let g = HlcGenerator::builder()
+            .clock(ManualClock::new(EPOCH + 10_000))
+            .max_drift(1000)
+            .max_lag(1000)
+            .build()
+            .unwrap();
+        assert!(
+            g.update(&HlcTimestamp::from_parts(EPOCH + 12_000, 0).unwrap())
+                .is_err()
+        );
+        assert!(
+            g.update(&HlcTimestamp::from_parts(EPOCH + 8000, 0).unwrap())
+                .is_err()
+        );
+    });
+
+    assert_eq!(events.len(), 2);
+    assert_event(&events[0], Level::WARN, "HLC drift exceeded", &[
+        ("incoming_pt", EPOCH + 12_000),
+        ("wall", EPOCH + 10_000),
+        ("skew", 2000),
+        ("max", 1000),
+    ]);
+    assert_eq!(events[0].1["direction"], "ahead");
+    assert_eq!(events[0].1["policy"], "reject");
+    assert_event(&events[1], Level::WARN, "HLC drift exceeded", &[
+        ("incoming_pt", EPOCH + 8000),
+        ("skew", -2000),
+    ]);
+    assert_eq!(events[1].1["direction"], "behind");
+}
+
+#[test]
+fn clock_regression() {
+    let events = capture(|| {
+        let g = HlcGenerator::manual(0);
+        g.set_current_timestamp(EPOCH + 10_000);
+        g.next_timestamp().unwrap();
+        g.set_current_timestamp(EPOCH + 9000);
+        // Physical time is kept, no matter how many timestamps are issued.
+        let ts = g.next_timestamp().unwrap();
+        assert_eq!(ts.timestamp(), EPOCH + 10_000);
+    });
+
+    assert_eq!(events.len(), 1);
+    assert_event(
+        &events[0],
+        Level::WARN,
+        "Wall-clock time went backwards, HLC physical time is kept",
+        &[
+            ("latest", EPOCH + 10_000),
+            ("wall", EPOCH + 9000),
+            ("regression", 1000),
+            ("pt", EPOCH + 10_000),
+        ],
+    );
+}
+
+#[test]
+fn no_false_regressions() {
+    // Local subscribers are per thread, so the global one is used (set once per
+    // test binary, and only this test relies on it).
+    let capture = Capture::default();
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(capture.clone()))
+        .unwrap();
+
+    let g = Arc::new(
+        HlcGenerator::builder()
+            .clock(SteadyClock(AtomicI64::new(EPOCH)))
+            .build()
+            .unwrap(),
+    );
+    let handles = (0..8)
+        .map(|_| {
+            let g = g.clone();
+            std::thread::spawn(move || {
+                for _ in 0..20_000 {
+                    g.next_timestamp().unwrap();
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+
+    // Readings delayed by the scheduling of the threads are not regressions.
+    assert_eq!(capture.0.lock().len(), 0);
+}
+
+#[test]
+fn counter_saturation() {
+    type Layout = BitLayout<42, 8>;
+
+    let events = capture(|| {
+        let g: HlcGenerator<ManualClock, Layout> = HlcGenerator::with_max_drift(0);
+        g.set_current_timestamp(EPOCH + 10_000);
+        let max = HlcTimestampOf::<Layout>::LC_MAX;
+        // Event is emitted only once, when the threshold is crossed.
+        g.next_timestamps(max - 10).unwrap();
+        for _ in 0..10 {
+            g.next_timestamp().unwrap();
+        }
+    });
+
+    assert_eq!(events.len(), 1);
+    assert_event(
+        &events[0],
+        Level::WARN,
+        "HLC logical clock is close to saturation",
+        &[("pt", EPOCH + 10_000), ("lc", 244), ("lc_max", 255)],
+    );
+}
+
+#[test]
+fn running_ahead() {
+    let events = capture(|| {
+        let g = HlcGenerator::manual(0);
+        g.set_current_timestamp(EPOCH + 10_000);
+        g.update(&HlcTimestamp::from_parts(EPOCH + 10_500, 0).unwrap())
+            .unwrap();
+        g.update(&HlcTimestamp::from_parts(EPOCH + 12_000, 0).unwrap())
+            .unwrap();
+        // Still ahead, but the threshold has already been crossed.
+        g.next_timestamp().unwrap();
+    });
+
+    assert_eq!(events.len(), 1);
+    assert_event(
+        &events[0],
+        Level::INFO,
+        "HLC physical time runs ahead of the wall-clock time",
+        &[
+            ("prev_pt", EPOCH + 10_500),
+            ("pt", EPOCH + 12_000),
+            ("wall", EPOCH + 10_000),
+            ("ahead", 2000),
+        ],
+    );
+    assert_eq!(events[0].1["event"], "receive");
+}